@@ -44,6 +44,10 @@ pub fn impl_attribute(_: TokenStream, item: TokenStream) -> TokenStream {
             fn as_name(&self) -> &str {
                 #attribute_name_string
             }
+
+            fn clone_box(&self) -> Box<dyn Attribute> {
+                Box::new(self.clone())
+            }
         }
 
         pub trait #attribute_trait: AttributesBox {
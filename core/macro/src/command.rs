@@ -1,3 +1,4 @@
+use convert_case::{Case, Casing};
 use proc_macro::TokenStream;
 use quote::quote;
 use syn::{parse, ItemStruct};
@@ -5,6 +6,10 @@ use syn::{parse, ItemStruct};
 pub fn impl_command(_: TokenStream, item: TokenStream) -> TokenStream {
     let item = parse::<ItemStruct>(item).unwrap();
     let command_type = &item.ident;
+    let command_name_string = command_type
+        .to_string()
+        .from_case(Case::Pascal)
+        .to_case(Case::Snake);
     let gen = quote! {
         // #[derive(Debug)]
         #item
@@ -21,6 +26,10 @@ pub fn impl_command(_: TokenStream, item: TokenStream) -> TokenStream {
             fn as_any_box(self: Box<Self>) -> Box<dyn Any> {
                 Box::new(self)
             }
+
+            fn as_name(&self) -> &str {
+                #command_name_string
+            }
         }
     };
     gen.into()
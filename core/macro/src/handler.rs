@@ -0,0 +1,26 @@
+pub use convert_case::{Case, Casing};
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{Ident, ItemFn};
+
+pub fn impl_handler(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let item_into: TokenStream2 = item.clone().into();
+    let command_type = syn::parse::<Ident>(attr).unwrap();
+    let command_name_string = command_type
+        .to_string()
+        .from_case(Case::Pascal)
+        .to_case(Case::Snake);
+    let handler_fn = syn::parse::<ItemFn>(item).unwrap();
+    let handler_fn_name = handler_fn.sig.ident;
+    let gen = quote! {
+        #item_into
+        let handler = Box::new(#handler_fn_name);
+        {
+            let mut game = game.lock().unwrap();
+            let mut handlers = game.handlers.write().unwrap();
+            handlers.insert(#command_name_string.to_string(), handler);
+        }
+    };
+    gen.into()
+}
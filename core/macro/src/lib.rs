@@ -6,6 +6,7 @@ mod attribute;
 mod command;
 mod effect;
 mod event;
+mod handler;
 mod system;
 mod task;
 
@@ -13,6 +14,7 @@ use attribute::impl_attribute;
 use command::impl_command;
 use effect::impl_effect;
 use event::impl_event;
+use handler::impl_handler;
 use system::impl_system;
 use task::impl_task;
 
@@ -38,6 +40,11 @@ pub fn event(attr: TokenStream, item: TokenStream) -> TokenStream {
     impl_event(attr, item)
 }
 
+#[proc_macro_attribute]
+pub fn handler(attr: TokenStream, item: TokenStream) -> TokenStream {
+    impl_handler(attr, item)
+}
+
 #[proc_macro]
 pub fn system(input: TokenStream) -> TokenStream {
     impl_system(input)
@@ -0,0 +1,103 @@
+use super::EntityId;
+
+struct Slot<T> {
+    generation: u32,
+    value: Option<T>,
+}
+
+/// Storage handing out ids that stay valid as long as their value is kept, slots are reused
+/// once a value is removed but ids of the removed values never match again.
+pub(crate) struct Arena<T> {
+    slots: Vec<Slot<T>>,
+    free: Vec<u32>,
+}
+
+impl<T> Arena<T> {
+    pub(crate) fn new() -> Self {
+        Self {
+            slots: Vec::new(),
+            free: Vec::new(),
+        }
+    }
+
+    pub(crate) fn insert(&mut self, value: T) -> EntityId {
+        match self.free.pop() {
+            Some(index) => {
+                let slot = &mut self.slots[index as usize];
+                slot.value = Some(value);
+                EntityId {
+                    index,
+                    generation: slot.generation,
+                }
+            }
+            None => {
+                self.slots.push(Slot {
+                    generation: 0,
+                    value: Some(value),
+                });
+                EntityId {
+                    index: (self.slots.len() - 1) as u32,
+                    generation: 0,
+                }
+            }
+        }
+    }
+
+    pub(crate) fn get(&self, id: EntityId) -> Option<&T> {
+        self.slots
+            .get(id.index as usize)
+            .filter(|slot| slot.generation == id.generation)?
+            .value
+            .as_ref()
+    }
+
+    pub(crate) fn get_mut(&mut self, id: EntityId) -> Option<&mut T> {
+        self.slots
+            .get_mut(id.index as usize)
+            .filter(|slot| slot.generation == id.generation)?
+            .value
+            .as_mut()
+    }
+
+    pub(crate) fn remove(&mut self, id: EntityId) -> Option<T> {
+        let slot = self
+            .slots
+            .get_mut(id.index as usize)
+            .filter(|slot| slot.generation == id.generation)?;
+        let value = slot.value.take()?;
+        slot.generation += 1;
+        self.free.push(id.index);
+        Some(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn removed_ids_do_not_match_the_reused_slot() {
+        let mut arena = Arena::new();
+        let first = arena.insert("first");
+        assert_eq!(arena.remove(first), Some("first"));
+
+        let second = arena.insert("second");
+        assert_eq!(second.index, first.index);
+        assert_ne!(second, first);
+        assert_eq!(arena.get(first), None);
+        assert_eq!(arena.get_mut(first), None);
+        assert_eq!(arena.remove(first), None);
+        assert_eq!(arena.get(second), Some(&"second"));
+    }
+
+    #[test]
+    fn ids_stay_valid_while_others_are_removed() {
+        let mut arena = Arena::new();
+        let kept = arena.insert(1);
+        let removed = arena.insert(2);
+        arena.remove(removed);
+        arena.insert(3);
+        assert_eq!(arena.get(kept), Some(&1));
+        assert_eq!(arena.remove(removed), None);
+    }
+}
@@ -1,10 +1,17 @@
-use std::{any::Any, collections::HashMap};
+use std::{any::Any, collections::HashMap, fmt::Debug};
 
-pub trait Attribute: Send + Sync {
+pub trait Attribute: Send + Sync + Debug {
     fn as_any(&self) -> &dyn Any;
     fn as_any_mut(&mut self) -> &mut dyn Any;
     fn as_name(&self) -> &str;
+    fn clone_box(&self) -> AttributeType;
 }
 
 pub type AttributeType = Box<dyn Attribute>;
 pub type AttributesType = HashMap<String, AttributeType>;
+
+impl Clone for AttributeType {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
+}
@@ -4,6 +4,7 @@ pub trait Command: Send + Sync {
     fn as_any(&self) -> &dyn Any;
     fn as_any_mut(&mut self) -> &mut dyn Any;
     fn as_any_box(self: Box<Self>) -> Box<dyn Any>;
+    fn as_name(&self) -> &str;
 }
 
 pub type CommandType = Box<dyn Command>;
@@ -1,4 +1,4 @@
-use super::{AttributeType, Command, Entity, EventType, Position, World};
+use super::{AttributeType, Command, Entity, EntityId, EventType, Position, World};
 use skyless_macro::command;
 use std::any::Any;
 
@@ -17,20 +17,25 @@ pub struct AddEntityCommand {
     pub entity: Entity,
 }
 
+#[command]
+pub struct RemoveEntityCommand {
+    pub entity: EntityId,
+}
+
 #[command]
 pub struct SetEntityAttributeCommand {
-    pub position: Position,
+    pub entity: EntityId,
     pub attribute: AttributeType,
 }
 
 #[command]
 pub struct RemoveEntityAttributeCommand {
-    pub position: Position,
+    pub entity: EntityId,
     pub attribute: AttributeType,
 }
 
 #[command]
 pub struct MoveEntityCommand {
-    pub from: Position,
+    pub entity: EntityId,
     pub to: Position,
 }
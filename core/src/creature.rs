@@ -0,0 +1,8 @@
+use super::{Attribute, AttributesBox, Entity, GameAttributes, Tile};
+use skyless_macro::attribute;
+use std::any::Any;
+
+/// Identifies a creature, players, monsters and NPCs alike, towards clients and everything addressing it.
+#[attribute]
+#[derive(PartialEq, Eq, Hash)]
+pub struct CreatureId(pub u32);
@@ -1,5 +1,7 @@
-use super::{CommandsType, EventType, GameAttributesType, TasksType, WorldType};
+use super::{CommandType, CommandsType, EventType, GameAttributesType, TasksType, WorldType};
 
 pub type EffectResultType = Option<(CommandsType, TasksType)>;
 pub type EffectType =
     Box<dyn Fn(EventType, GameAttributesType, WorldType) -> EffectResultType + Send>;
+pub type HandlerType =
+    Box<dyn Fn(CommandType, GameAttributesType, WorldType) -> EffectResultType + Send>;
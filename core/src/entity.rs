@@ -1,6 +1,7 @@
 use super::{AttributesBox, AttributesType};
 use std::collections::HashMap;
 
+#[derive(Debug, Clone)]
 pub struct Entity {
     pub attributes: AttributesType,
 }
@@ -13,6 +14,14 @@ impl Entity {
     }
 }
 
+/// Stable handle of an entity in the world, it keeps addressing the entity wherever it is moved
+/// and never addresses another one once the entity is removed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct EntityId {
+    pub(crate) index: u32,
+    pub(crate) generation: u32,
+}
+
 impl AttributesBox for Entity {
     fn attributes(&self) -> &AttributesType {
         &self.attributes
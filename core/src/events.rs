@@ -1,24 +1,39 @@
-use super::{Event, Position};
+use super::{EntityId, Event, Position};
 use skyless_macro::event;
 use std::any::Any;
 
 #[event]
 pub struct SystemsLoadedEvent;
 
+#[event]
+pub struct AddedEntityEvent {
+    pub entity: EntityId,
+}
+
+/// The entity left the world, `position` is where it was, stack position included.
+#[event]
+pub struct DespawnedEntityEvent {
+    pub entity: EntityId,
+    pub position: Position,
+}
+
+/// The entity is now on the tile at `to`, `from` is where it was, stack position included.
 #[event]
 pub struct MovedEntityEvent {
+    pub entity: EntityId,
     pub from: Position,
     pub to: Position,
 }
 
 #[event]
 pub struct ChangedEntityEvent {
-    pub position: Position,
+    pub entity: EntityId,
     pub attribute_name: String,
 }
 
+/// The attribute was taken off the entity, which stays in the world.
 #[event]
 pub struct RemovedEntityEvent {
-    pub position: Position,
+    pub entity: EntityId,
     pub attribute_name: String,
 }
@@ -1,6 +1,6 @@
 use super::{
     commands::*, events::*, AttributeType, AttributesBox, AttributesType, CommandType, EffectType,
-    Entity, EventType, Position, TaskType, World, WorldType,
+    Entity, EntityId, EventType, HandlerType, Position, TaskType, World, WorldType,
 };
 use std::collections::HashMap;
 use std::future::Future;
@@ -32,6 +32,7 @@ pub type GameAttributesType = Arc<Mutex<GameAttributes>>;
 pub struct Game {
     pub attributes: GameAttributesType,
     pub listeners: Rc<RwLock<HashMap<String, Vec<EffectType>>>>,
+    pub handlers: Rc<RwLock<HashMap<String, HandlerType>>>,
     pub tasker: Sender<TaskType>,
     pub world: WorldType,
 }
@@ -41,6 +42,7 @@ impl Game {
         Self {
             attributes: Arc::new(Mutex::new(GameAttributes::new())),
             listeners: Rc::new(RwLock::new(HashMap::new())),
+            handlers: Rc::new(RwLock::new(HashMap::new())),
             tasker,
             world: Arc::new(Mutex::new(World::new())),
         }
@@ -74,29 +76,75 @@ impl Game {
                 .as_any_box()
                 .downcast::<Box<AddEntityCommand>>()
                 .unwrap();
-            self.add_entity(position, entity);
+            return self.add_entity(position, entity);
+        } else if command_box
+            .as_any()
+            .downcast_ref::<RemoveEntityCommand>()
+            .is_some()
+        {
+            let RemoveEntityCommand { entity } = **command_box
+                .as_any_box()
+                .downcast::<Box<RemoveEntityCommand>>()
+                .unwrap();
+            return self.remove_entity(entity);
         } else if let Some(_) = command_box.as_any().downcast_ref::<MoveEntityCommand>() {
-            let MoveEntityCommand { from, to } = **command_box
+            let MoveEntityCommand { entity, to } = **command_box
                 .as_any_box()
                 .downcast::<Box<MoveEntityCommand>>()
                 .unwrap();
-            return self.move_entity(from, to);
+            return self.move_entity(entity, to);
         } else if let Some(_) = command_box
             .as_any()
             .downcast_ref::<SetEntityAttributeCommand>()
         {
-            let SetEntityAttributeCommand {
-                position,
-                attribute,
-            } = **command_box
+            let SetEntityAttributeCommand { entity, attribute } = **command_box
                 .as_any_box()
                 .downcast::<Box<SetEntityAttributeCommand>>()
                 .unwrap();
-            return self.set_entity_attribute(position, attribute);
+            return self.set_entity_attribute(entity, attribute);
+        } else if command_box
+            .as_any()
+            .downcast_ref::<RemoveEntityAttributeCommand>()
+            .is_some()
+        {
+            let RemoveEntityAttributeCommand { entity, attribute } = **command_box
+                .as_any_box()
+                .downcast::<Box<RemoveEntityAttributeCommand>>()
+                .unwrap();
+            return self.remove_entity_attribute(entity, attribute);
+        } else {
+            return self.handle_command(command_box);
         }
         Box::pin(async {})
     }
 
+    // commands defined outside of core are passed to the handler registered by their system
+    fn handle_command(
+        &mut self,
+        command_box: CommandType,
+    ) -> Pin<Box<dyn Future<Output = ()> + '_>> {
+        Box::pin(async move {
+            let command_name = command_box.as_name().to_string();
+            let result = self
+                .handlers
+                .clone()
+                .read()
+                .unwrap()
+                .get(&command_name)
+                .and_then(|handler| {
+                    handler(command_box, self.attributes.clone(), self.world.clone())
+                });
+            if let Some((commands, tasks)) = result {
+                for command in commands.into_iter() {
+                    self.process(command).await;
+                }
+                for task in tasks.into_iter() {
+                    self.tasker.send(task).await.unwrap(); // FIXME: unwrap
+                }
+            }
+        })
+    }
+
     fn set_attribute(&mut self, attribute: AttributeType) {
         let mut attributes = self.attributes.lock().unwrap();
         let key = attribute.as_name().to_string();
@@ -109,26 +157,44 @@ impl Game {
         // TODO: emit event
     }
 
-    fn add_entity(&mut self, position: Position, entity: Entity) {
-        if let Some(tile) = self.world.lock().unwrap().0.get_mut(&position) {
-            tile.entities.push(entity);
-            // TODO: emit event
-        }
+    fn add_entity(
+        &mut self,
+        position: Position,
+        entity: Entity,
+    ) -> Pin<Box<dyn Future<Output = ()> + '_>> {
+        Box::pin(async move {
+            if let Some(event) = (|| {
+                let mut world = self.world.lock().unwrap();
+                let entity = world.add_entity(&position, entity)?;
+                Some(Arc::new(AddedEntityEvent { entity }) as EventType)
+            })() {
+                self.emit_event(event).await;
+            }
+        })
+    }
+
+    fn remove_entity(&mut self, entity: EntityId) -> Pin<Box<dyn Future<Output = ()> + '_>> {
+        Box::pin(async move {
+            if let Some(event) = (|| {
+                let mut world = self.world.lock().unwrap();
+                let (_, position) = world.remove_entity(entity)?;
+                Some(Arc::new(DespawnedEntityEvent { entity, position }) as EventType)
+            })() {
+                self.emit_event(event).await;
+            }
+        })
     }
 
     fn move_entity(
         &mut self,
-        from: Position,
+        entity: EntityId,
         to: Position,
     ) -> Pin<Box<dyn Future<Output = ()> + '_>> {
         Box::pin(async move {
             if let Some(event) = (|| {
                 let mut world = self.world.lock().unwrap();
-                let from_tile = world.tile_mut(&from)?;
-                let entity = from_tile.entities.remove(from.stack_pos? as usize);
-                let to_tile = world.tile_mut(&to)?;
-                to_tile.entities.push(entity);
-                Some(Arc::new(MovedEntityEvent { from, to }) as EventType)
+                let from = world.move_entity(entity, &to)?;
+                Some(Arc::new(MovedEntityEvent { entity, from, to }) as EventType)
             })() {
                 self.emit_event(event).await;
             }
@@ -137,17 +203,51 @@ impl Game {
 
     fn set_entity_attribute(
         &mut self,
-        position: Position,
+        entity: EntityId,
+        attribute: AttributeType,
+    ) -> Pin<Box<dyn Future<Output = ()> + '_>> {
+        Box::pin(async move {
+            if let Some(events) = (|| {
+                let mut world = self.world.lock().unwrap();
+                let attribute_name = attribute.as_name().to_string();
+                world
+                    .entity_mut(entity)?
+                    .attributes
+                    .insert(attribute_name.clone(), attribute);
+                let mut events = Vec::new();
+                // an entity changing layers leaves its place on the tile and is put into the new one
+                if let Some(position) = world.restack(entity) {
+                    events.push(Arc::new(DespawnedEntityEvent { entity, position }) as EventType);
+                    events.push(Arc::new(AddedEntityEvent { entity }) as EventType);
+                }
+                events.push(Arc::new(ChangedEntityEvent {
+                    entity,
+                    attribute_name,
+                }) as EventType);
+                Some(events)
+            })() {
+                for event in events {
+                    self.emit_event(event).await;
+                }
+            }
+        })
+    }
+
+    fn remove_entity_attribute(
+        &mut self,
+        entity: EntityId,
         attribute: AttributeType,
     ) -> Pin<Box<dyn Future<Output = ()> + '_>> {
         Box::pin(async move {
             if let Some(event) = (|| {
                 let mut world = self.world.lock().unwrap();
-                let entity = world.entity_mut(&position)?;
                 let attribute_name = attribute.as_name().to_string();
-                entity.attributes.insert(attribute_name.clone(), attribute);
-                Some(Arc::new(ChangedEntityEvent {
-                    position,
+                world
+                    .entity_mut(entity)?
+                    .attributes
+                    .remove(&attribute_name)?;
+                Some(Arc::new(RemovedEntityEvent {
+                    entity,
                     attribute_name,
                 }) as EventType)
             })() {
@@ -156,10 +256,6 @@ impl Game {
         })
     }
 
-    // fn remove_entity_attribute(&mut self, position: Position, attribute: AttributeType) {
-    //     // TODO: emit event
-    // }
-
     fn emit_event(&mut self, event: EventType) -> Pin<Box<dyn Future<Output = ()> + '_>> {
         // println!("Event {:?}", event);
         Box::pin(async move {
@@ -1,9 +1,11 @@
 #![forbid(unsafe_code)]
 
+mod arena;
 mod attribute;
 mod attributes_box;
 mod command;
 mod commands;
+mod creature;
 mod effect;
 pub mod entity;
 mod event;
@@ -19,12 +21,13 @@ pub use attribute::*;
 pub use attributes_box::*;
 pub use command::*;
 pub use commands::*;
+pub use creature::*;
 pub use effect::*;
-pub use entity::Entity;
+pub use entity::{Entity, EntityId};
 pub use event::*;
 pub use events::*;
 pub use game::*;
 pub use position::*;
 pub use task::*;
-pub use tile::Tile;
+pub use tile::{StackOrder, Tile};
 pub use world::*;
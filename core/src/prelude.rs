@@ -1,11 +1,11 @@
 pub use super::{
-    commands::*, events::*, position::*, Attribute, AttributesBox, Command, CommandType,
-    CommandsType, EffectResultType, Entity, Event, EventType, EventsType, Game, GameAttributes,
-    GameAttributesType, TaskType, Tile, WorldType,
+    commands::*, creature::*, events::*, position::*, Attribute, AttributesBox, Command,
+    CommandType, CommandsType, EffectResultType, Entity, EntityId, Event, EventType, EventsType,
+    Game, GameAttributes, GameAttributesType, StackOrder, TaskType, Tile, WorldType,
 };
 pub use crate::entity;
 pub use async_stream::stream;
-pub use skyless_macro::{attribute, command, effect, event, system, task};
+pub use skyless_macro::{attribute, command, effect, event, handler, system, task};
 pub use std::any::Any;
 pub use std::collections::HashMap;
 pub use std::io::Result as IoResult;
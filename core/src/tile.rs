@@ -1,8 +1,21 @@
-use super::{AttributesBox, AttributesType, Entity};
+use super::{AttributesBox, AttributesType, EntityId};
 
 pub struct Tile {
     pub attributes: AttributesType,
-    pub entities: Vec<Entity>,
+    /// Entities on the tile in the order clients stack them, their index is the stack position.
+    pub entities: Vec<EntityId>,
+}
+
+/// Layers of a tile as clients stack them, an entity goes before everything of a later layer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum StackOrder {
+    Ground,
+    /// Things drawn over creatures, such as borders and walls, the number orders them among
+    /// themselves.
+    Top(u8),
+    Creature,
+    /// Everything else, the latest one is on top of the pile.
+    Bottom,
 }
 
 impl AttributesBox for Tile {
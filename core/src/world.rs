@@ -1,31 +1,458 @@
-use super::{Entity, Position, Tile};
-use std::collections::HashMap;
+use super::{
+    arena::Arena, CreatureId, CreatureIdTrait, Entity, EntityId, Position, StackOrder, Tile,
+};
+use std::collections::{HashMap, HashSet};
 use std::sync::{Arc, Mutex};
 
-pub struct World(pub HashMap<Position, Tile>);
+// creatures are grouped by squares of that many tiles on each floor, so range queries only
+// look at the few squares they overlap instead of the whole map
+const SECTOR_SIZE: u16 = 8;
+
+type Sector = (u16, u16, u8);
+
+fn sector(position: &Position) -> Sector {
+    (
+        position.x / SECTOR_SIZE,
+        position.y / SECTOR_SIZE,
+        position.z,
+    )
+}
+
+struct Placed {
+    entity: Entity,
+    // the tile, the stack position is where the id is found on it
+    position: Position,
+}
+
+#[derive(Default)]
+struct Creatures {
+    entities: HashMap<CreatureId, EntityId>,
+    sectors: HashMap<Sector, HashSet<CreatureId>>,
+}
+
+impl Creatures {
+    fn place(
+        &mut self,
+        creature: &CreatureId,
+        id: EntityId,
+        from: Option<&Position>,
+        to: &Position,
+    ) {
+        if let Some(from) = from {
+            if sector(from) == sector(to) {
+                return;
+            }
+            self.leave(creature, from);
+        }
+        self.entities.insert(creature.clone(), id);
+        self.sectors
+            .entry(sector(to))
+            .or_default()
+            .insert(creature.clone());
+    }
+
+    fn forget(&mut self, creature: &CreatureId, position: &Position) {
+        self.entities.remove(creature);
+        self.leave(creature, position);
+    }
+
+    fn leave(&mut self, creature: &CreatureId, position: &Position) {
+        let sector = sector(position);
+        if let Some(creatures) = self.sectors.get_mut(&sector) {
+            creatures.remove(creature);
+            if creatures.is_empty() {
+                self.sectors.remove(&sector);
+            }
+        }
+    }
+}
+
+fn creatures_above(entity: &Entity) -> StackOrder {
+    match entity.creature_id() {
+        Some(_) => StackOrder::Creature,
+        None => StackOrder::Bottom,
+    }
+}
+
+/// Tiles of the map and the entities on them, each entity addressed by its own `EntityId`.
+///
+/// Entities only enter, leave and change tiles through `add_entity`, `remove_entity` and
+/// `move_entity`, which keep the creature index up to date and the tiles in stack order. Entities
+/// are expected to keep their `CreatureId` while they are in the world, and to be put back into
+/// their layer with `restack` whenever a change of their attributes changes it.
+pub struct World {
+    tiles: HashMap<Position, Tile>,
+    entities: Arena<Placed>,
+    creatures: Creatures,
+    stack_order: fn(&Entity) -> StackOrder,
+}
 
 pub type WorldType = Arc<Mutex<World>>;
 
 impl World {
     pub fn new() -> Self {
-        Self(HashMap::new())
+        Self {
+            tiles: HashMap::new(),
+            entities: Arena::new(),
+            creatures: Creatures::default(),
+            stack_order: creatures_above,
+        }
+    }
+
+    /// Uses `stack_order` to tell the layer of entities, only creatures are told apart otherwise.
+    pub fn ordered_by(mut self, stack_order: fn(&Entity) -> StackOrder) -> Self {
+        self.stack_order = stack_order;
+        self
+    }
+
+    /// Creates the tile with the entities on it, each one put on top of the ones before.
+    pub fn add_tile(&mut self, position: Position, entities: Vec<Entity>) {
+        let position = position.stack_pos(None);
+        self.tiles.insert(
+            position.clone(),
+            Tile {
+                attributes: HashMap::new(),
+                entities: Vec::new(),
+            },
+        );
+        for entity in entities {
+            self.add_entity(&position, entity);
+        }
     }
 
     pub fn tile(&self, position: &Position) -> Option<&Tile> {
-        self.0.get(&position.clone().stack_pos(None))
+        self.tiles.get(&position.clone().stack_pos(None))
+    }
+
+    pub fn tiles(&self) -> impl Iterator<Item = (&Position, &Tile)> {
+        self.tiles.iter()
+    }
+
+    pub fn entity(&self, id: EntityId) -> Option<&Entity> {
+        Some(&self.entities.get(id)?.entity)
     }
 
-    pub fn tile_mut(&mut self, position: &Position) -> Option<&mut Tile> {
-        self.0.get_mut(&position.clone().stack_pos(None))
+    pub fn entity_mut(&mut self, id: EntityId) -> Option<&mut Entity> {
+        Some(&mut self.entities.get_mut(id)?.entity)
     }
 
-    pub fn entity(&self, position: &Position) -> Option<&Entity> {
+    /// The entity at the stack position of the tile, as addressed by clients.
+    pub fn entity_at(&self, position: &Position) -> Option<EntityId> {
         let tile = self.tile(position)?;
-        tile.entities.get(position.stack_pos? as usize)
+        tile.entities.get(position.stack_pos? as usize).copied()
+    }
+
+    /// Entities on the tile, from the bottom to the top.
+    pub fn entities(
+        &self,
+        position: &Position,
+    ) -> impl DoubleEndedIterator<Item = (EntityId, &Entity)> {
+        self.tile(position)
+            .into_iter()
+            .flat_map(|tile| tile.entities.iter())
+            .filter_map(|id| Some((*id, self.entity(*id)?)))
+    }
+
+    /// Where the entity is, stack position included.
+    pub fn position(&self, id: EntityId) -> Option<Position> {
+        let position = &self.entities.get(id)?.position;
+        let tile = self.tiles.get(position)?;
+        let stack_pos = tile.entities.iter().position(|other| *other == id)?;
+        Some(position.clone().stack_pos(Some(stack_pos as u16)))
+    }
+
+    pub fn find_creature(&self, creature: &CreatureId) -> Option<EntityId> {
+        self.creatures.entities.get(creature).copied()
+    }
+
+    pub fn creatures(&self) -> impl Iterator<Item = (&CreatureId, EntityId)> {
+        self.creatures
+            .entities
+            .iter()
+            .map(|(creature, id)| (creature, *id))
     }
 
-    pub fn entity_mut(&mut self, position: &Position) -> Option<&mut Entity> {
-        let tile = self.tile_mut(position)?;
-        tile.entities.get_mut(position.stack_pos? as usize)
+    /// Creatures standing within the box spanned by `from` and `to`, both corners included.
+    pub fn creatures_in(
+        &self,
+        from: Position,
+        to: Position,
+    ) -> impl Iterator<Item = (&CreatureId, EntityId)> {
+        let ((from_x, from_y, from_z), (to_x, to_y, to_z)) = (sector(&from), sector(&to));
+        (from_z..=to_z)
+            .flat_map(move |z| {
+                (from_y..=to_y).flat_map(move |y| (from_x..=to_x).map(move |x| (x, y, z)))
+            })
+            .filter_map(|sector| self.creatures.sectors.get(&sector))
+            .flatten()
+            .filter_map(|creature| {
+                let (creature, id) = self.creatures.entities.get_key_value(creature)?;
+                Some((creature, *id, &self.entities.get(*id)?.position))
+            })
+            .filter(move |(_, _, position)| {
+                (from.x..=to.x).contains(&position.x)
+                    && (from.y..=to.y).contains(&position.y)
+                    && (from.z..=to.z).contains(&position.z)
+            })
+            .map(|(creature, id, _)| (creature, id))
+    }
+
+    // top things keep the order they came in, any other layer has its latest entity first
+    fn stack_index(&self, tile: &Tile, entity: &Entity) -> usize {
+        let order = (self.stack_order)(entity);
+        tile.entities
+            .iter()
+            .position(|other| {
+                self.entity(*other).is_some_and(|other| match order {
+                    StackOrder::Top(_) => (self.stack_order)(other) > order,
+                    _ => (self.stack_order)(other) >= order,
+                })
+            })
+            .unwrap_or(tile.entities.len())
+    }
+
+    /// Puts the entity on top of its layer of the tile.
+    pub fn add_entity(&mut self, position: &Position, entity: Entity) -> Option<EntityId> {
+        let position = position.clone().stack_pos(None);
+        let index = self.stack_index(self.tiles.get(&position)?, &entity);
+        let creature = entity.creature_id().cloned();
+        let id = self.entities.insert(Placed {
+            entity,
+            position: position.clone(),
+        });
+        self.tiles.get_mut(&position)?.entities.insert(index, id);
+        if let Some(creature) = creature {
+            self.creatures.place(&creature, id, None, &position);
+        }
+        Some(id)
+    }
+
+    /// Takes the entity out of the world, along with where it was.
+    pub fn remove_entity(&mut self, id: EntityId) -> Option<(Entity, Position)> {
+        let position = self.position(id)?;
+        let Placed {
+            entity,
+            position: tile_position,
+        } = self.entities.remove(id)?;
+        self.tiles
+            .get_mut(&tile_position)?
+            .entities
+            .remove(position.stack_pos? as usize);
+        if let Some(creature) = entity.creature_id() {
+            self.creatures.forget(creature, &tile_position);
+        }
+        Some((entity, position))
+    }
+
+    /// Puts the entity back on top of its layer when it is not between the layers below and
+    /// above it anymore, and returns where it was then.
+    pub fn restack(&mut self, id: EntityId) -> Option<Position> {
+        let from = self.position(id)?;
+        let tile_position = from.clone().stack_pos(None);
+        let stack_pos = from.stack_pos? as usize;
+        let tile = self.tiles.get(&tile_position)?;
+        let order = (self.stack_order)(self.entity(id)?);
+        let order_at = |index: usize| {
+            let other = self.entity(*tile.entities.get(index)?)?;
+            Some((self.stack_order)(other))
+        };
+        let below = stack_pos.checked_sub(1).and_then(order_at);
+        let above = order_at(stack_pos + 1);
+        if below.is_none_or(|below| below <= order) && above.is_none_or(|above| above >= order) {
+            return None;
+        }
+        self.tiles
+            .get_mut(&tile_position)?
+            .entities
+            .remove(stack_pos);
+        let index = self.stack_index(self.tiles.get(&tile_position)?, self.entity(id)?);
+        self.tiles
+            .get_mut(&tile_position)?
+            .entities
+            .insert(index, id);
+        Some(from)
+    }
+
+    /// Puts the entity on top of its layer of the other tile and returns where it was.
+    pub fn move_entity(&mut self, id: EntityId, to: &Position) -> Option<Position> {
+        let to = to.clone().stack_pos(None);
+        self.tiles.get(&to)?;
+        let from = self.position(id)?;
+        let tile_position = from.clone().stack_pos(None);
+        self.tiles
+            .get_mut(&tile_position)?
+            .entities
+            .remove(from.stack_pos? as usize);
+        let index = self.stack_index(self.tiles.get(&to)?, self.entity(id)?);
+        self.tiles.get_mut(&to)?.entities.insert(index, id);
+        let placed = self.entities.get_mut(id)?;
+        placed.position = to.clone();
+        if let Some(creature) = placed.entity.creature_id().cloned() {
+            self.creatures
+                .place(&creature, id, Some(&tile_position), &to);
+        }
+        Some(from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{entity, Attribute, AttributesBox, GameAttributes};
+    use skyless_macro::attribute;
+    use std::any::Any;
+
+    #[attribute]
+    struct Layer(StackOrder);
+
+    fn layered(entity: &Entity) -> StackOrder {
+        match entity.layer() {
+            Some(Layer(order)) => *order,
+            None => creatures_above(entity),
+        }
+    }
+
+    fn thing(order: StackOrder) -> Entity {
+        entity![Layer(order)]
+    }
+
+    fn creature(id: u32) -> Entity {
+        entity![CreatureId(id)]
+    }
+
+    fn stack(world: &World, position: &Position) -> Vec<EntityId> {
+        world.entities(position).map(|(id, _)| id).collect()
+    }
+
+    fn creatures_in(world: &World, from: Position, to: Position) -> HashSet<u32> {
+        world
+            .creatures_in(from, to)
+            .map(|(creature, _)| creature.0)
+            .collect()
+    }
+
+    #[test]
+    fn creatures_are_found_after_moving_and_not_after_removal() {
+        let mut world = World::new();
+        let (from, to) = (Position(100, 100, 7), Position(120, 100, 7));
+        world.add_tile(from.clone(), Vec::new());
+        world.add_tile(to.clone(), Vec::new());
+        let id = world.add_entity(&from, creature(1)).unwrap();
+        assert_eq!(world.find_creature(&CreatureId(1)), Some(id));
+
+        assert_eq!(world.move_entity(id, &to), Some(from.stack_pos(Some(0))));
+        assert_eq!(world.find_creature(&CreatureId(1)), Some(id));
+        assert_eq!(world.position(id), Some(to.clone().stack_pos(Some(0))));
+
+        assert!(world.remove_entity(id).is_some());
+        assert_eq!(world.find_creature(&CreatureId(1)), None);
+        assert!(creatures_in(&world, Position(0, 0, 7), Position(200, 200, 7)).is_empty());
+        assert_eq!(world.creatures().count(), 0);
+    }
+
+    #[test]
+    fn creatures_in_spans_sectors_and_leaves_out_what_is_beyond_the_box() {
+        let mut world = World::new();
+        let creatures = [
+            Position(7, 7, 7),
+            Position(8, 8, 7),
+            Position(15, 15, 7),
+            Position(16, 8, 7),
+            Position(8, 6, 7),
+            Position(8, 8, 8),
+        ];
+        for (id, position) in creatures.iter().enumerate() {
+            world.add_tile(position.clone(), vec![creature(id as u32)]);
+        }
+        let (from, to) = (Position(7, 7, 7), Position(15, 15, 7));
+        assert_eq!(
+            creatures_in(&world, from.clone(), to.clone()),
+            HashSet::from([0, 1, 2])
+        );
+
+        // moving into another sector of the box keeps the creature in it
+        let id = world.find_creature(&CreatureId(1)).unwrap();
+        world.move_entity(id, &Position(15, 15, 7));
+        assert_eq!(
+            creatures_in(&world, from.clone(), to.clone()),
+            HashSet::from([0, 1, 2])
+        );
+        world.move_entity(id, &Position(16, 8, 7));
+        assert_eq!(creatures_in(&world, from, to), HashSet::from([0, 2]));
+        assert_eq!(
+            creatures_in(&world, Position(0, 0, 7), Position(16, 16, 8)),
+            HashSet::from([0, 1, 2, 3, 4, 5])
+        );
+    }
+
+    #[test]
+    fn entities_are_stacked_by_layer() {
+        let mut world = World::new().ordered_by(layered);
+        let position = Position(100, 100, 7);
+        world.add_tile(position.clone(), Vec::new());
+        let mut add = |entity| world.add_entity(&position, entity).unwrap();
+        let first_item = add(thing(StackOrder::Bottom));
+        let first_creature = add(creature(1));
+        let wall = add(thing(StackOrder::Top(2)));
+        let first_border = add(thing(StackOrder::Top(1)));
+        let second_item = add(thing(StackOrder::Bottom));
+        let second_creature = add(creature(2));
+        let second_border = add(thing(StackOrder::Top(1)));
+        let ground = add(thing(StackOrder::Ground));
+        assert_eq!(
+            stack(&world, &position),
+            vec![
+                ground,
+                first_border,
+                second_border,
+                wall,
+                second_creature,
+                first_creature,
+                second_item,
+                first_item,
+            ]
+        );
+        assert_eq!(
+            world.entity_at(&position.clone().stack_pos(Some(3))),
+            Some(wall)
+        );
+        assert_eq!(
+            world.position(first_item),
+            Some(position.stack_pos(Some(7)))
+        );
+    }
+
+    #[test]
+    fn restack_moves_only_entities_out_of_their_layer() {
+        let mut world = World::new().ordered_by(layered);
+        let position = Position(100, 100, 7);
+        world.add_tile(
+            position.clone(),
+            vec![
+                thing(StackOrder::Ground),
+                creature(1),
+                thing(StackOrder::Bottom),
+                thing(StackOrder::Bottom),
+            ],
+        );
+        let [ground, creature, latest_item, first_item] = stack(&world, &position)[..] else {
+            panic!("four entities are on the tile");
+        };
+        assert_eq!(world.restack(first_item), None);
+
+        world
+            .entity_mut(first_item)
+            .unwrap()
+            .attributes
+            .insert("layer".to_string(), Box::new(Layer(StackOrder::Top(1))));
+        assert_eq!(
+            world.restack(first_item),
+            Some(position.clone().stack_pos(Some(3)))
+        );
+        assert_eq!(
+            stack(&world, &position),
+            vec![ground, first_item, creature, latest_item]
+        );
+        assert_eq!(world.restack(first_item), None);
     }
 }
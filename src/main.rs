@@ -40,6 +40,39 @@ async fn main() -> Result<()> {
     skyless_systems_core::map::hardcoded::system(game.clone(), tasks_sender.clone())
         .await
         .unwrap();
+    skyless_systems_core::decay::system(game.clone(), tasks_sender.clone())
+        .await
+        .unwrap();
+    skyless_systems_core::combat::system(game.clone(), tasks_sender.clone())
+        .await
+        .unwrap();
+    skyless_systems_core::conditions::system(game.clone(), tasks_sender.clone())
+        .await
+        .unwrap();
+    skyless_systems_core::progression::system(game.clone(), tasks_sender.clone())
+        .await
+        .unwrap();
+    skyless_systems_core::regeneration::system(game.clone(), tasks_sender.clone())
+        .await
+        .unwrap();
+    skyless_systems_core::spells::system(game.clone(), tasks_sender.clone())
+        .await
+        .unwrap();
+    skyless_systems_core::monsters::system(game.clone(), tasks_sender.clone())
+        .await
+        .unwrap();
+    skyless_systems_core::ai::system(game.clone(), tasks_sender.clone())
+        .await
+        .unwrap();
+    skyless_systems_core::npcs::system(game.clone(), tasks_sender.clone())
+        .await
+        .unwrap();
+    skyless_systems_core::shops::system(game.clone(), tasks_sender.clone())
+        .await
+        .unwrap();
+    skyless_systems_core::persistence::system(game.clone(), tasks_sender.clone())
+        .await
+        .unwrap();
     skyless_systems_core::network::login_server::system(game.clone(), tasks_sender.clone())
         .await
         .unwrap();
@@ -52,6 +85,21 @@ async fn main() -> Result<()> {
     skyless_systems_core::network::handlers::r#move::system(game.clone(), tasks_sender.clone())
         .await
         .unwrap();
+    skyless_systems_core::network::handlers::channels::system(game.clone(), tasks_sender.clone())
+        .await
+        .unwrap();
+    skyless_systems_core::network::handlers::combat::system(game.clone(), tasks_sender.clone())
+        .await
+        .unwrap();
+    skyless_systems_core::network::handlers::effects::system(game.clone(), tasks_sender.clone())
+        .await
+        .unwrap();
+    skyless_systems_core::network::handlers::shop::system(game.clone(), tasks_sender.clone())
+        .await
+        .unwrap();
+    skyless_systems_core::network::handlers::talk::system(game.clone(), tasks_sender.clone())
+        .await
+        .unwrap();
     skyless_systems_core::network::handlers::use_item::system(game.clone(), tasks_sender.clone())
         .await
         .unwrap();
@@ -61,6 +109,18 @@ async fn main() -> Result<()> {
     skyless_scripts::switch::system(game.clone(), tasks_sender.clone())
         .await
         .unwrap();
+    skyless_scripts::link::system(game.clone(), tasks_sender.clone())
+        .await
+        .unwrap();
+    skyless_scripts::door::system(game.clone(), tasks_sender.clone())
+        .await
+        .unwrap();
+    skyless_scripts::teleport::system(game.clone(), tasks_sender.clone())
+        .await
+        .unwrap();
+    skyless_scripts::npc::system(game.clone(), tasks_sender.clone())
+        .await
+        .unwrap();
     // skyless_scripts::tick::system(game.clone(), tasks_sender.clone())
     //     .await
     //     .unwrap();
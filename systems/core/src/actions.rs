@@ -2,4 +2,5 @@
 pub enum Actions {
     Lever = 1,
     Switch = 2,
+    Door = 3,
 }
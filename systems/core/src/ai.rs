@@ -0,0 +1,327 @@
+use crate::{combat, geometry, prelude::*};
+use skyless_core::{prelude::*, World};
+use std::{
+    cmp::Reverse,
+    time::{Duration, Instant},
+};
+use tokio::time;
+
+const THINK_INTERVAL: Duration = Duration::from_millis(250);
+const ATTACK_INTERVAL: Duration = Duration::from_secs(2);
+// about as far as a client sees
+const SIGHT_RANGE: u16 = 8;
+// chance in percent to take a step while wandering around
+const WANDER_CHANCE: u8 = 25;
+
+/// Players on the same floor the creature at `position` can notice.
+fn players_around(world: &World, position: &Position) -> Vec<(CreatureId, Position)> {
+    let from = Position(
+        position.x.saturating_sub(SIGHT_RANGE),
+        position.y.saturating_sub(SIGHT_RANGE),
+        position.z,
+    );
+    let to = Position(
+        position.x.saturating_add(SIGHT_RANGE),
+        position.y.saturating_add(SIGHT_RANGE),
+        position.z,
+    );
+    world
+        .creatures_in(from, to)
+        .filter(|(_, id)| world.entity(*id).and_then(Entity::player).is_some())
+        .filter_map(|(creature, id)| Some((creature.clone(), world.position(id)?)))
+        .collect()
+}
+
+/// Whoever hurt the monster the most is attacked first, the closest player otherwise.
+fn select_target(
+    world: &World,
+    position: &Position,
+    entity: &Entity,
+    players: Vec<(CreatureId, Position)>,
+) -> Option<(CreatureId, Position)> {
+    let damage_by = |creature: &CreatureId| {
+        entity
+            .damages()
+            .and_then(|Damages(damages)| damages.get(creature).copied())
+            .unwrap_or(0)
+    };
+    players
+        .into_iter()
+        .filter(|(_, player_position)| geometry::is_sight_clear(world, position, player_position))
+        .max_by_key(|(creature, player_position)| {
+            (
+                damage_by(creature),
+                Reverse(geometry::distance(position, player_position)),
+            )
+        })
+}
+
+fn state(
+    monster_type: &MonsterType,
+    entity: &Entity,
+    has_players: bool,
+    has_target: bool,
+) -> AiStates {
+    let health = entity.health().map_or(0, |Health { value, .. }| *value);
+    let melee = monster_type.attacks.iter().any(|attack| attack.range <= 1);
+    match (has_players, has_target) {
+        (false, _) => AiStates::Idle,
+        (true, false) => AiStates::Wander,
+        _ if health <= monster_type.flee_health => AiStates::Flee,
+        _ if !melee && !monster_type.attacks.is_empty() => AiStates::KeepDistance,
+        _ => AiStates::Chase,
+    }
+}
+
+/// Next step for the state, the free neighbouring tile closest to what the state is after.
+fn step(
+    world: &World,
+    position: &Position,
+    state: AiStates,
+    target: Option<&Position>,
+    range: u16,
+) -> Option<Position> {
+    let neighbours: Vec<Position> = geometry::in_range(position, 1)
+        .filter(|neighbour| geometry::is_walkable(world, neighbour))
+        .collect();
+    let score = |from: &Position| -> Option<i32> {
+        let distance = i32::from(geometry::distance(from, target?));
+        let manhattan = i32::from(geometry::manhattan_distance(from, target?));
+        match state {
+            AiStates::Chase => Some(distance * 2 + manhattan),
+            AiStates::KeepDistance => Some((distance - i32::from(range)).abs()),
+            AiStates::Flee => Some(-distance),
+            _ => None,
+        }
+    };
+    match state {
+        AiStates::Idle => None,
+        AiStates::Wander => (fastrand::u8(0..100) < WANDER_CHANCE)
+            .then(|| fastrand::choice(neighbours))
+            .flatten(),
+        AiStates::Chase if target.is_some_and(|target| geometry::is_adjacent(position, target)) => {
+            None
+        }
+        _ => {
+            let current = score(position)?;
+            neighbours
+                .into_iter()
+                .filter_map(|neighbour| Some((score(&neighbour)?, neighbour)))
+                .filter(|(neighbour_score, _)| *neighbour_score < current)
+                .min_by_key(|(neighbour_score, _)| *neighbour_score)
+                .map(|(_, neighbour)| neighbour)
+        }
+    }
+}
+
+fn attack(
+    world: &World,
+    creature: &CreatureId,
+    position: &Position,
+    attack: &MonsterAttack,
+    target: &CreatureId,
+    target_position: &Position,
+) -> Option<CommandsType> {
+    let target_id = world.find_creature(target)?;
+    let target_entity = world.entity(target_id)?;
+    let mut commands = Vec::new();
+    if let Some(shoot) = attack.shoot {
+        commands.push(Box::new(ShowEffectCommand {
+            position: position.clone(),
+            effect: VisualEffects::Distance {
+                to: target_position.clone(),
+                shoot,
+            },
+        }) as CommandType);
+    }
+    let damage = fastrand::u16(attack.min..=attack.max);
+    // only physical hits can be fended off
+    let blocked = if attack.effect.is_none() {
+        combat::block(target_entity)
+    } else {
+        0
+    };
+    let dealt = damage.saturating_sub(blocked);
+    if target_entity.player().is_some() {
+        commands.push(Box::new(AddConditionCommand {
+            player: target.clone(),
+            condition: ConditionType::InFight,
+            duration: combat::IN_FIGHT,
+            damage: 0,
+        }) as CommandType);
+    }
+    if dealt == 0 {
+        let effect = if damage > 0 {
+            MagicEffects::BlockHit
+        } else {
+            MagicEffects::Poff
+        };
+        commands.push(Box::new(ShowEffectCommand {
+            position: target_position.clone(),
+            effect: VisualEffects::Magic(effect),
+        }) as CommandType);
+    } else {
+        let effect = attack.effect.unwrap_or(MagicEffects::DrawBlood);
+        commands.append(&mut combat::damage(
+            world,
+            target_id,
+            dealt,
+            effect,
+            TextColors::Red,
+        ));
+    }
+    commands.push(Box::new(EmitEventCommand(Arc::new(HitEvent {
+        attacker: creature.clone(),
+        target: target.clone(),
+        damage: dealt,
+    }))) as CommandType);
+    Some(commands)
+}
+
+fn think(
+    world: &World,
+    monster_types: &MonsterTypes,
+    id: EntityId,
+    entity: &Entity,
+) -> Option<CommandsType> {
+    let Monster(name) = entity.monster()?;
+    let position = &world.position(id)?;
+    let monster_type = monster_types.0.get(name)?;
+    let creature = entity.creature_id()?;
+    let now = Instant::now();
+    let mut commands = Vec::new();
+
+    let players = players_around(world, position);
+    let has_players = !players.is_empty();
+    let target = select_target(world, position, entity, players);
+    let state = state(monster_type, entity, has_players, target.is_some());
+
+    match (&target, entity.target()) {
+        (Some((target, _)), current)
+            if current.is_none_or(|current| current.creature != *target) =>
+        {
+            commands.push(Box::new(SetEntityAttributeCommand {
+                entity: id,
+                attribute: Box::new(Target {
+                    creature: target.clone(),
+                    mode: TargetModes::Attack,
+                    since: now,
+                }),
+            }) as CommandType);
+        }
+        (None, Some(current)) => {
+            commands.push(Box::new(RemoveEntityAttributeCommand {
+                entity: id,
+                attribute: Box::new(current.clone()),
+            }) as CommandType);
+        }
+        _ => {}
+    }
+
+    let attack_ready = entity
+        .attacking()
+        .is_none_or(|Attacking { until }| *until <= now);
+    if let Some((target, target_position)) = target.as_ref().filter(|_| attack_ready) {
+        let distance = geometry::distance(position, target_position);
+        let usable: Vec<&MonsterAttack> = monster_type
+            .attacks
+            .iter()
+            .filter(|attack| attack.range >= distance)
+            .collect();
+        if let Some(chosen) = fastrand::choice(usable) {
+            commands.push(Box::new(SetEntityAttributeCommand {
+                entity: id,
+                attribute: Box::new(Attacking {
+                    until: now + ATTACK_INTERVAL,
+                }),
+            }) as CommandType);
+            // the cooldown and the rest of the turn are kept even when the attack cannot be made
+            if let Some(mut hit) =
+                attack(world, creature, position, chosen, target, target_position)
+            {
+                commands.append(&mut hit);
+            }
+        }
+    }
+
+    let walk_ready = entity
+        .walking()
+        .is_none_or(|Walking { until }| *until <= now);
+    let range = monster_type
+        .attacks
+        .iter()
+        .map(|attack| attack.range)
+        .max()
+        .unwrap_or(1);
+    if let Some(to) = step(
+        world,
+        position,
+        state,
+        target.as_ref().map(|(_, target_position)| target_position),
+        range,
+    )
+    .filter(|_| walk_ready)
+    {
+        commands.push(Box::new(SetEntityAttributeCommand {
+            entity: id,
+            attribute: Box::new(Walking {
+                until: now + combat::step_duration(entity),
+            }),
+        }) as CommandType);
+        commands.push(Box::new(EmitEventCommand(Arc::new(MoveEvent {
+            from: position.clone().stack_pos(None),
+            to,
+            creature: creature.clone(),
+        }))) as CommandType);
+    }
+    Some(commands)
+}
+
+system! {
+    #[event]
+    pub struct ThinkEvent;
+
+    task! {
+        let mut interval = time::interval(THINK_INTERVAL);
+
+        loop {
+            interval.tick().await;
+            yield Some(Arc::new(ThinkEvent) as EventType);
+        }
+    }
+
+    #[effect(ThinkEvent)]
+    fn handle_think(_: EventType, attributes: GameAttributesType, world: WorldType) -> EffectResultType {
+        let game_attributes = attributes.lock().unwrap();
+        let world = world.lock().unwrap();
+        let Some(monster_types) = game_attributes.monster_types() else {
+            return Some((Vec::new(), Vec::new()));
+        };
+        let commands = world
+            .creatures()
+            .filter_map(|(_, id)| Some((id, world.entity(id).filter(|entity| entity.monster().is_some())?)))
+            .filter_map(|(id, entity)| think(&world, monster_types, id, entity))
+            .flatten()
+            .collect();
+        Some((commands, Vec::new()))
+    }
+
+    #[effect(HitEvent)]
+    fn handle_monster_hit(event: EventType, _: GameAttributesType, world: WorldType) -> EffectResultType {
+        let HitEvent { attacker, target, damage } = event.as_any().downcast_ref::<HitEvent>().unwrap();
+        let world = world.lock().unwrap();
+        let commands = (|| {
+            let id = world.find_creature(target)?;
+            let entity = world.entity(id)?;
+            entity.monster()?;
+            let mut damages = entity.damages().cloned().unwrap_or_else(|| Damages(HashMap::new()));
+            *damages.0.entry(attacker.clone()).or_insert(0) += u32::from(*damage);
+            Some(vec![Box::new(SetEntityAttributeCommand {
+                entity: id,
+                attribute: Box::new(damages),
+            }) as CommandType])
+        })()
+        .unwrap_or_default();
+        Some((commands, Vec::new()))
+    }
+}
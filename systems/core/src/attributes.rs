@@ -1,19 +1,364 @@
 use crate::prelude::*;
-use skyless_core::prelude::*;
-use std::time::Instant;
+use itertools::Itertools;
+use serde::Deserialize;
+use skyless_core::{prelude::*, World};
+use std::collections::HashSet;
+use std::time::{Duration, Instant, SystemTime};
 
 #[attribute]
 pub struct Action(pub Actions);
 
+#[attribute]
+pub struct ActionId(pub u16);
+
 #[attribute]
 pub struct Item(pub u16);
 
+impl Item {
+    pub fn is_blocking(&self) -> bool {
+        Items::try_from(self.0).is_ok_and(|item| item.is_blocking())
+    }
+}
+
+#[attribute]
+pub struct Count(pub u8);
+
+/// When the item turns into the next one, a wall-clock time so the remaining part can be saved
+/// and the timer rebuilt from it.
+#[attribute]
+pub struct Decay {
+    pub until: SystemTime,
+}
+
+impl Decay {
+    pub fn after(remaining: Duration) -> Self {
+        Self {
+            until: SystemTime::now() + remaining,
+        }
+    }
+
+    pub fn remaining(&self) -> Duration {
+        self.until
+            .duration_since(SystemTime::now())
+            .unwrap_or_default()
+    }
+}
+
+#[attribute]
+pub struct Door(pub Doors);
+
+impl Door {
+    /// Whether the creature may stand in the doorway, level and quest doors only let some players through.
+    pub fn can_pass(&self, entity: &Entity) -> bool {
+        match &self.0 {
+            Doors::Regular => true,
+            Doors::Level(level) => entity.level().is_some_and(|Level(value)| value >= level),
+            Doors::Quest(key) => entity
+                .storage()
+                .is_some_and(|Storage(storage)| storage.get(key).is_some_and(|value| *value > 0)),
+        }
+    }
+}
+
+#[attribute]
+pub struct Teleport(pub Position);
+
+#[attribute]
+pub struct Link {
+    pub targets: Vec<LinkTarget>,
+    pub reset: Option<Duration>,
+}
+
 #[attribute]
 pub struct Name(pub String);
 
+/// Marks the creature as a player, it is told apart from others by its `CreatureId`.
+#[attribute]
+pub struct Player;
+
+impl Player {
+    pub fn find_by_name(world: &World, name: &str) -> Option<(CreatureId, EntityId)> {
+        world.creatures().find_map(|(creature, id)| {
+            let entity = world.entity(id)?;
+            entity.player()?;
+            let Name(entity_name) = entity.name()?;
+            entity_name
+                .eq_ignore_ascii_case(name)
+                .then(|| (creature.clone(), id))
+        })
+    }
+}
+
+/// Account owning the player's character, kept to save the character back to it.
+#[attribute]
+pub struct Account(pub u32);
+
+/// Names of every saved character in lower case, whether they are online or not.
+#[attribute]
+pub struct CharacterNames(pub HashSet<String>);
+
+impl CharacterNames {
+    pub fn contains(&self, name: &str) -> bool {
+        self.0.contains(&name.to_lowercase())
+    }
+}
+
+/// Next free creature id, every creature entering the game gets its own.
+#[attribute]
+pub struct CreatureIds(pub u32);
+
+impl CreatureIds {
+    // clients use the lower ids for other things
+    pub const FIRST: u32 = 0x10000000;
+
+    pub fn allocate(&mut self) -> CreatureId {
+        let id = CreatureId(self.0);
+        self.0 += 1;
+        id
+    }
+}
+
+impl Default for CreatureIds {
+    fn default() -> Self {
+        Self(Self::FIRST)
+    }
+}
+
+/// Name of the monster type the creature was spawned from.
+#[attribute]
+pub struct Monster(pub String);
+
+/// Defense of a creature fighting without items, like monsters.
+#[attribute]
+pub struct Defense(pub u16);
+
+/// Items kept inside, such as the loot lying in a corpse.
+#[attribute]
+pub struct Container(pub Vec<Entity>);
+
+impl Container {
+    /// Slots shown by the client, more are added when there are more items.
+    pub const SLOTS: usize = 8;
+}
+
+/// Damage dealt to the monster so far by each attacker.
+#[attribute]
+pub struct Damages(pub HashMap<CreatureId, u32>);
+
+#[attribute]
+pub struct MonsterTypes(pub HashMap<String, MonsterType>);
+
+/// Spawn area the monster belongs to, it comes back there `interval` after dying.
+#[attribute]
+pub struct Spawn {
+    pub center: Position,
+    pub radius: u16,
+    pub interval: Duration,
+}
+
+/// Name of the NPC type the creature was created from.
+#[attribute]
+pub struct Npc(pub String);
+
+#[attribute]
+pub struct NpcTypes(pub HashMap<String, NpcType>);
+
+/// Player an NPC is talking to, the conversation ends when nothing was said for a while.
+#[attribute]
+pub struct Focus {
+    pub player: CreatureId,
+    pub last: Instant,
+}
+
+/// NPC whose trade window the player has open.
+#[attribute]
+pub struct Shop(pub CreatureId);
+
+#[attribute]
+pub struct Level(pub u16);
+
+impl Level {
+    /// Total experience needed to reach the given level.
+    pub fn experience_for(level: u16) -> u64 {
+        let level = u64::from(level.max(1)) - 1;
+        // the terms are added before subtracting, the total is never negative
+        50u64
+            .saturating_mul(level.saturating_pow(3))
+            .saturating_add(400 * level)
+            .saturating_sub(150 * level.pow(2))
+            / 3
+    }
+
+    pub fn progress(&self, Experience(experience): &Experience) -> u8 {
+        let current = Self::experience_for(self.0);
+        percent(
+            experience.saturating_sub(current),
+            Self::experience_for(self.0.saturating_add(1)).saturating_sub(current),
+        )
+    }
+}
+
+#[attribute]
+pub struct Experience(pub u64);
+
+#[attribute]
+pub struct MagicLevel {
+    pub level: u16,
+    pub mana_spent: u64,
+}
+
+impl MagicLevel {
+    const MANA_MULTIPLIER: f64 = 4.0;
+
+    /// Mana to spend to advance from `level - 1` to `level`.
+    pub fn mana_for(level: u16) -> u64 {
+        (1600.0 * Self::MANA_MULTIPLIER.powi(i32::from(level) - 1)) as u64
+    }
+
+    pub fn progress(&self) -> u8 {
+        percent(self.mana_spent, Self::mana_for(self.level + 1))
+    }
+}
+
+#[attribute]
+pub struct Skills(pub HashMap<SkillType, Skill>);
+
+impl Skills {
+    pub fn get(&self, skill: SkillType) -> Skill {
+        self.0.get(&skill).copied().unwrap_or_default()
+    }
+}
+
+#[attribute]
+pub struct Storage(pub HashMap<u32, i32>);
+
+#[attribute]
+pub struct Inventory(pub HashMap<InventorySlot, Entity>);
+
+impl Inventory {
+    pub const POSITION_X: u16 = 0xFFFF;
+
+    pub fn position(slot: InventorySlot) -> Position {
+        Position(Self::POSITION_X, u8::from(slot).into(), 0)
+    }
+
+    pub fn slot(position: &Position) -> Option<InventorySlot> {
+        if position.x != Self::POSITION_X {
+            return None;
+        }
+        InventorySlot::try_from(u8::try_from(position.y).ok()?).ok()
+    }
+
+    pub fn at(&self, position: &Position) -> Option<&Entity> {
+        self.0.get(&Self::slot(position)?)
+    }
+
+    /// Total weight of carried items, in hundredths of an ounce.
+    pub fn weight(&self) -> u32 {
+        self.0
+            .values()
+            .filter_map(|entity| {
+                let item = Items::try_from(entity.item()?.0).ok()?;
+                let count = entity.count().map_or(1, |Count(count)| u32::from(*count));
+                Some(item.weight() * count)
+            })
+            .sum()
+    }
+
+    /// How many of the item are carried, stacks added up.
+    pub fn count(&self, item: Items) -> u32 {
+        self.0
+            .values()
+            .filter(|entity| entity.item().is_some_and(|Item(id)| *id == u16::from(item)))
+            .map(|entity| entity.count().map_or(1, |Count(count)| u32::from(*count)))
+            .sum()
+    }
+
+    // carried slots in a fixed order, so the same stacks are always picked first
+    fn slots(&self, item: Items) -> Vec<InventorySlot> {
+        self.0
+            .iter()
+            .filter(|(_, entity)| entity.item().is_some_and(|Item(id)| *id == u16::from(item)))
+            .map(|(slot, _)| *slot)
+            .sorted_by_key(|slot| u8::from(*slot))
+            .collect()
+    }
+
+    /// Puts `count` of the item onto the carried stacks, then into the free slots where things can be stored.
+    /// Nothing changes when there is no room for all of them.
+    pub fn add(&mut self, item: Items, count: u32) -> bool {
+        let mut inventory = self.clone();
+        let mut left = count;
+        if item.is_stackable() {
+            for slot in inventory.slots(item) {
+                let entity = inventory.0.get_mut(&slot).unwrap();
+                let current = entity.count().map_or(1, |Count(count)| *count);
+                let added = left.min(u32::from(Items::MAX_STACK.saturating_sub(current))) as u8;
+                let count = Count(current + added);
+                entity
+                    .attributes
+                    .insert(count.as_name().to_string(), Box::new(count));
+                left -= u32::from(added);
+            }
+        }
+        for slot in [
+            InventorySlot::Backpack,
+            InventorySlot::Right,
+            InventorySlot::Left,
+            InventorySlot::Ammo,
+        ] {
+            if left == 0 {
+                break;
+            }
+            if inventory.0.contains_key(&slot) {
+                continue;
+            }
+            let entity = if item.is_stackable() {
+                let count = left.min(u32::from(Items::MAX_STACK)) as u8;
+                left -= u32::from(count);
+                entity![Item(item.into()), Count(count)]
+            } else {
+                left -= 1;
+                entity![Item(item.into())]
+            };
+            inventory.0.insert(slot, entity);
+        }
+        if left > 0 {
+            return false;
+        }
+        *self = inventory;
+        true
+    }
+
+    /// Takes `count` of the item away, nothing changes when not that many are carried.
+    pub fn remove(&mut self, item: Items, count: u32) -> bool {
+        if self.count(item) < count {
+            return false;
+        }
+        let mut left = count;
+        for slot in self.slots(item) {
+            if left == 0 {
+                break;
+            }
+            let entity = self.0.get_mut(&slot).unwrap();
+            let current = entity.count().map_or(1, |Count(count)| *count);
+            if u32::from(current) > left {
+                let count = Count(current - left as u8);
+                entity
+                    .attributes
+                    .insert(count.as_name().to_string(), Box::new(count));
+                left = 0;
+            } else {
+                self.0.remove(&slot);
+                left -= u32::from(current);
+            }
+        }
+        true
+    }
+}
+
 #[attribute]
-#[derive(PartialEq, Eq, Hash)]
-pub struct Player(pub u32);
+pub struct Channels(pub HashMap<u16, Channel>);
 
 #[attribute]
 pub struct LightInfo {
@@ -21,6 +366,17 @@ pub struct LightInfo {
     pub color: u8,
 }
 
+impl LightInfo {
+    pub const NONE: Self = Self {
+        level: 0x00,
+        color: 0x00,
+    };
+    pub const TORCH: Self = Self {
+        level: 6,
+        color: 215,
+    };
+}
+
 // FIXME: remove this? cause we now have different health
 // and probably we can get rid of most of attrs here, if not all?
 // I mean, we need to remove systems/core, and use new modules structure instead
@@ -30,6 +386,69 @@ pub struct Health {
     pub max: u16,
 }
 
+impl Health {
+    pub fn percent(&self) -> u8 {
+        (self.value as f64 / self.max as f64 * 100.0).ceil() as u8
+    }
+}
+
+#[attribute]
+pub struct Mana {
+    pub value: u16,
+    pub max: u16,
+}
+
+#[attribute]
+pub struct Soul(pub u8);
+
+impl Soul {
+    pub const MAX: u8 = 100;
+}
+
+/// Maximum capacity in hundredths of an ounce, the free one depends on what is carried.
+#[attribute]
+pub struct Capacity(pub u32);
+
+impl Capacity {
+    pub fn free(&self, inventory: Option<&Inventory>) -> u32 {
+        self.0
+            .saturating_sub(inventory.map_or(0, Inventory::weight))
+    }
+}
+
+/// Remaining stamina in minutes.
+#[attribute]
+pub struct Stamina(pub u16);
+
+#[attribute]
+pub struct Conditions(pub HashMap<ConditionType, Condition>);
+
+impl Conditions {
+    pub fn has(&self, condition: ConditionType) -> bool {
+        self.0.contains_key(&condition)
+    }
+}
+
+#[attribute]
+pub struct Cooldowns(pub HashMap<String, Instant>);
+
+#[attribute]
+pub struct Spells(pub Vec<Spell>);
+
+impl Spells {
+    pub fn by_words(&self, words: &str) -> Option<&Spell> {
+        self.0
+            .iter()
+            .find(|spell| matches!(&spell.trigger, SpellTriggers::Words(spell_words) if spell_words.eq_ignore_ascii_case(words)))
+    }
+
+    pub fn by_rune(&self, rune: Items) -> Option<&Spell> {
+        self.0
+            .iter()
+            .find(|spell| spell.trigger == SpellTriggers::Rune(rune))
+    }
+}
+
 #[attribute]
 pub struct Direction(pub Directions);
 
@@ -63,9 +482,23 @@ impl Direction {
             _ => None,
         }
     }
+
+    /// First step of the way from `first` towards `second` on the same floor.
+    pub fn towards(first: Position, second: Position) -> Option<Self> {
+        let (x, y, z) = first.diff(second);
+        if z != 0 {
+            return None;
+        }
+        let step = first
+            .clone()
+            .x((first.x as i32 + x.signum()) as u16)
+            .y((first.y as i32 + y.signum()) as u16);
+        Self::between_positions(first, step)
+    }
 }
 
 #[attribute]
+#[derive(Deserialize)]
 pub struct Outfit {
     pub r#type: u16,
     pub head: u8,
@@ -88,3 +521,36 @@ pub struct PartyShield(pub PartyShields);
 pub struct Walking {
     pub until: Instant,
 }
+
+#[attribute]
+pub struct Attacking {
+    pub until: Instant,
+}
+
+#[attribute]
+pub struct Target {
+    pub creature: CreatureId,
+    pub mode: TargetModes,
+    pub since: Instant,
+}
+
+#[attribute]
+pub struct AttackSpeed(pub Duration);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn experience_keeps_growing_up_to_the_last_level() {
+        assert_eq!(Level::experience_for(1), 0);
+        assert_eq!(Level::experience_for(2), 100);
+        assert!(Level::experience_for(u16::MAX) > Level::experience_for(u16::MAX - 1));
+    }
+
+    #[test]
+    fn progress_of_the_last_level_does_not_overflow() {
+        assert_eq!(Level(u16::MAX).progress(&Experience(u64::MAX)), 0);
+        assert_eq!(Level(u16::MAX - 1).progress(&Experience(u64::MAX)), 100);
+    }
+}
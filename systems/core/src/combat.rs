@@ -0,0 +1,492 @@
+use crate::{conditions, geometry, prelude::*};
+use skyless_core::{prelude::*, World};
+use std::time::{Duration, Instant};
+use tokio::time;
+
+pub const TEMPLE: Position = Position {
+    x: 128,
+    y: 128,
+    z: 7,
+    stack_pos: None,
+};
+
+const DEAD: &str = "You are dead.";
+const FIST_ATTACK: u16 = 7;
+const GROUND_SPEED: u64 = 150;
+const DEFAULT_ATTACK_SPEED: Duration = Duration::from_millis(2000);
+pub const IN_FIGHT: Duration = Duration::from_secs(60);
+
+#[event]
+pub struct AttackEvent {
+    pub attacker: CreatureId,
+    pub since: Instant,
+}
+
+#[event]
+pub struct FollowEvent {
+    pub follower: CreatureId,
+    pub since: Instant,
+}
+
+fn schedule_attack(attacker: CreatureId, since: Instant, delay: Duration) -> TaskType {
+    Box::pin(stream! {
+        time::sleep(delay).await;
+        yield Some(Arc::new(AttackEvent { attacker, since }) as EventType);
+    })
+}
+
+fn schedule_follow(follower: CreatureId, since: Instant, delay: Duration) -> TaskType {
+    Box::pin(stream! {
+        time::sleep(delay).await;
+        yield Some(Arc::new(FollowEvent { follower, since }) as EventType);
+    })
+}
+
+fn attack_speed(entity: &Entity) -> Duration {
+    entity
+        .attack_speed()
+        .map_or(DEFAULT_ATTACK_SPEED, |AttackSpeed(speed)| *speed)
+}
+
+pub fn step_duration(entity: &Entity) -> Duration {
+    let speed = entity.speed().map_or(1, |Speed(speed)| (*speed).max(1));
+    Duration::from_millis((1000 * GROUND_SPEED) / speed as u64)
+}
+
+fn skill(entity: &Entity, skill: SkillType) -> u16 {
+    entity
+        .skills()
+        .map_or(Skill::MIN_LEVEL, |skills| skills.get(skill).level)
+}
+
+enum Weapon {
+    Melee {
+        skill: SkillType,
+        attack: u16,
+    },
+    Distance {
+        range: u16,
+        attack: u16,
+        shoot: ShootTypes,
+        slot: InventorySlot,
+    },
+}
+
+fn hand_items(entity: &Entity) -> Vec<(InventorySlot, Items)> {
+    let Some(Inventory(inventory)) = entity.inventory() else {
+        return Vec::new();
+    };
+    [InventorySlot::Left, InventorySlot::Right]
+        .into_iter()
+        .filter_map(|slot| Some((slot, Items::try_from(inventory.get(&slot)?.item()?.0).ok()?)))
+        .collect()
+}
+
+/// Weapon the creature fights with, `None` when a launcher is held without matching ammunition.
+fn weapon(entity: &Entity) -> Option<Weapon> {
+    let hands = hand_items(entity);
+    if let Some((slot, item, range)) = hands
+        .iter()
+        .find_map(|(slot, item)| Some((*slot, *item, item.range()?)))
+    {
+        let (slot, ammo) = match item.ammo() {
+            Some(ammo) => {
+                let Inventory(inventory) = entity.inventory()?;
+                let loaded =
+                    Items::try_from(inventory.get(&InventorySlot::Ammo)?.item()?.0).ok()?;
+                (
+                    InventorySlot::Ammo,
+                    Some(loaded).filter(|loaded| *loaded == ammo)?,
+                )
+            }
+            None => (slot, item),
+        };
+        let (_, attack) = ammo.attack()?;
+        return Some(Weapon::Distance {
+            range,
+            attack,
+            shoot: ammo.shoot()?,
+            slot,
+        });
+    }
+    let (skill, attack) = hands
+        .iter()
+        .find_map(|(_, item)| {
+            item.attack()
+                .filter(|(skill, _)| *skill != SkillType::Distance)
+        })
+        .unwrap_or((SkillType::Fist, FIST_ATTACK));
+    Some(Weapon::Melee { skill, attack })
+}
+
+fn can_reach(world: &World, weapon: &Weapon, from: &Position, to: &Position) -> bool {
+    match weapon {
+        Weapon::Melee { .. } => geometry::is_adjacent(from, to),
+        Weapon::Distance { range, .. } => {
+            from.z == to.z
+                && geometry::distance(from, to) <= *range
+                && geometry::is_sight_clear(world, from, to)
+        }
+    }
+}
+
+/// Chance in percent, it drops the further away the target stands.
+fn hit_chance(skill: u16, distance: u16) -> u16 {
+    (skill.min(75) + 15).saturating_sub(distance.saturating_sub(1) * 5)
+}
+
+fn max_weapon_damage(skill: u16, attack: u16) -> u16 {
+    (skill as f64 * attack as f64 * 0.05 + attack as f64 * 0.5).ceil() as u16
+}
+
+fn max_defense(skill: u16, defense: u16) -> u16 {
+    (skill as f64 * defense as f64 * 0.015 + defense as f64 * 0.1).ceil() as u16
+}
+
+fn consume(id: EntityId, entity: &Entity, slot: InventorySlot) -> Option<CommandType> {
+    let Inventory(mut inventory) = entity.inventory()?.clone();
+    let mut item = inventory.remove(&slot)?;
+    let count = item.count().map_or(1, |Count(count)| *count);
+    if count > 1 {
+        let count = Count(count - 1);
+        item.attributes
+            .insert(count.as_name().to_string(), Box::new(count));
+        inventory.insert(slot, item);
+    }
+    Some(Box::new(SetEntityAttributeCommand {
+        entity: id,
+        attribute: Box::new(Inventory(inventory)),
+    }) as CommandType)
+}
+
+/// Random part of a physical hit the creature fends off with what it holds.
+pub fn block(entity: &Entity) -> u16 {
+    let defense = hand_items(entity)
+        .iter()
+        .filter_map(|(_, item)| item.defense())
+        .max()
+        .or(entity.defense().map(|Defense(defense)| *defense))
+        .unwrap_or(0);
+    fastrand::u16(0..=max_defense(skill(entity, SkillType::Shield), defense))
+}
+
+/// Takes `amount` from the creature, from its mana first while a mana shield is up.
+pub fn damage(
+    world: &World,
+    id: EntityId,
+    amount: u16,
+    effect: MagicEffects,
+    color: TextColors,
+) -> CommandsType {
+    let mut commands = Vec::new();
+    let (Some(entity), Some(position)) = (world.entity(id), world.position(id)) else {
+        return commands;
+    };
+    let mut amount = amount;
+    let shielded = entity
+        .conditions()
+        .is_some_and(|conditions| conditions.has(ConditionType::ManaShield));
+    if let Some(Mana { value, max }) = entity.mana().filter(|_| shielded) {
+        let absorbed = amount.min(*value);
+        if absorbed > 0 {
+            amount -= absorbed;
+            commands.push(Box::new(SetEntityAttributeCommand {
+                entity: id,
+                attribute: Box::new(Mana {
+                    value: value - absorbed,
+                    max: *max,
+                }),
+            }) as CommandType);
+            commands.push(Box::new(ShowEffectCommand {
+                position: position.clone(),
+                effect: VisualEffects::Magic(MagicEffects::LoseEnergy),
+            }) as CommandType);
+            commands.push(Box::new(ShowEffectCommand {
+                position: position.clone(),
+                effect: VisualEffects::AnimatedText {
+                    color: TextColors::Blue,
+                    text: absorbed.to_string(),
+                },
+            }) as CommandType);
+        }
+    }
+    let Some(Health { value, max }) = entity.health().filter(|_| amount > 0) else {
+        return commands;
+    };
+    let amount = amount.min(*value);
+    commands.push(Box::new(SetEntityAttributeCommand {
+        entity: id,
+        attribute: Box::new(Health {
+            value: value - amount,
+            max: *max,
+        }),
+    }) as CommandType);
+    commands.push(Box::new(ShowEffectCommand {
+        position: position.clone(),
+        effect: VisualEffects::Magic(effect),
+    }) as CommandType);
+    commands.push(Box::new(ShowEffectCommand {
+        position: position.clone(),
+        effect: VisualEffects::AnimatedText {
+            color,
+            text: amount.to_string(),
+        },
+    }) as CommandType);
+    if amount > 0 && amount == *value {
+        commands
+            .push(Box::new(EmitEventCommand(Arc::new(DeathEvent { entity: id }))) as CommandType);
+    }
+    commands
+}
+
+/// Makes everybody stop attacking or following `creature`, and `creature` stop attacking or following anyone.
+pub fn untarget(world: &World, creature: &CreatureId) -> CommandsType {
+    let mut commands = Vec::new();
+    for (attacker, id) in world.creatures() {
+        let Some(target) = world.entity(id).and_then(Entity::target) else {
+            continue;
+        };
+        if attacker == creature || target.creature == *creature {
+            commands.push(Box::new(RemoveEntityAttributeCommand {
+                entity: id,
+                attribute: Box::new(target.clone()),
+            }) as CommandType);
+        }
+    }
+    commands
+}
+
+fn strike(
+    world: &World,
+    attacker_id: EntityId,
+    weapon: &Weapon,
+    target_id: EntityId,
+) -> Option<CommandsType> {
+    let (attacker_entity, position) = (world.entity(attacker_id)?, world.position(attacker_id)?);
+    let (target_entity, target_position) = (world.entity(target_id)?, world.position(target_id)?);
+    let (attacker, target) = (attacker_entity.creature_id()?, target_entity.creature_id()?);
+    let mut commands = Vec::new();
+
+    let damage = match weapon {
+        Weapon::Melee {
+            skill: skill_type,
+            attack,
+        } => fastrand::u16(0..=max_weapon_damage(skill(attacker_entity, *skill_type), *attack)),
+        Weapon::Distance {
+            attack,
+            shoot,
+            slot,
+            ..
+        } => {
+            commands.push(Box::new(ShowEffectCommand {
+                position: position.clone(),
+                effect: VisualEffects::Distance {
+                    to: target_position.clone(),
+                    shoot: *shoot,
+                },
+            }) as CommandType);
+            commands.extend(consume(attacker_id, attacker_entity, *slot));
+            let skill = skill(attacker_entity, SkillType::Distance);
+            if fastrand::u16(0..100)
+                < hit_chance(skill, geometry::distance(&position, &target_position))
+            {
+                fastrand::u16(0..=max_weapon_damage(skill, *attack))
+            } else {
+                0
+            }
+        }
+    };
+    let blocked = block(target_entity);
+    let dealt = damage.saturating_sub(blocked);
+
+    let trained = match weapon {
+        Weapon::Melee { skill, .. } => *skill,
+        Weapon::Distance { .. } => SkillType::Distance,
+    };
+    commands.push(Box::new(AddSkillTriesCommand {
+        player: attacker.clone(),
+        skill: trained,
+        tries: 1,
+    }) as CommandType);
+    let target_player = target_entity.player().and(Some(target));
+    if let Some(target_player) = target_player.filter(|_| damage > 0 && blocked > 0) {
+        commands.push(Box::new(AddSkillTriesCommand {
+            player: target_player.clone(),
+            skill: SkillType::Shield,
+            tries: 1,
+        }) as CommandType);
+    }
+
+    for player in [Some(attacker), target_player].into_iter().flatten() {
+        commands.push(Box::new(AddConditionCommand {
+            player: player.clone(),
+            condition: ConditionType::InFight,
+            duration: IN_FIGHT,
+            damage: 0,
+        }) as CommandType);
+    }
+
+    if dealt == 0 {
+        let effect = if damage > 0 {
+            MagicEffects::BlockHit
+        } else {
+            MagicEffects::Poff
+        };
+        commands.push(Box::new(ShowEffectCommand {
+            position: target_position.clone(),
+            effect: VisualEffects::Magic(effect),
+        }) as CommandType);
+    } else {
+        commands.append(&mut self::damage(
+            world,
+            target_id,
+            dealt,
+            MagicEffects::DrawBlood,
+            TextColors::Red,
+        ));
+    }
+    commands.push(Box::new(EmitEventCommand(Arc::new(HitEvent {
+        attacker: attacker.clone(),
+        target: target.clone(),
+        damage: dealt,
+    }))) as CommandType);
+    Some(commands)
+}
+
+system! {
+    // every new target restarts the loop, older loops stop once they see a different `since`
+    #[effect(ChangedEntityEvent)]
+    fn handle_target_changed(event: EventType, _: GameAttributesType, world: WorldType) -> EffectResultType {
+        let ChangedEntityEvent { entity, attribute_name } = event.as_any().downcast_ref::<ChangedEntityEvent>().unwrap();
+        let mut tasks = Vec::new();
+        if attribute_name.as_str() != "target" {
+            return Some((Vec::new(), tasks));
+        }
+        let world = world.lock().unwrap();
+        if let Some(task) = (|| {
+            let entity = world.entity(*entity)?;
+            entity.player()?;
+            let player = entity.creature_id()?.clone();
+            let Target { mode, since, .. } = entity.target()?;
+            Some(match mode {
+                TargetModes::Attack => schedule_attack(player, *since, attack_speed(entity)),
+                TargetModes::Follow => schedule_follow(player, *since, Duration::ZERO),
+            })
+        })() {
+            tasks.push(task);
+        }
+        Some((Vec::new(), tasks))
+    }
+
+    #[effect(AttackEvent)]
+    fn handle_attack(event: EventType, _: GameAttributesType, world: WorldType) -> EffectResultType {
+        let AttackEvent { attacker, since } = event.as_any().downcast_ref::<AttackEvent>().unwrap();
+        let mut commands = Vec::new();
+        let mut tasks = Vec::new();
+        let world = world.lock().unwrap();
+
+        if let Some((mut attack_commands, task)) = (|| {
+            let id = world.find_creature(attacker)?;
+            let entity = world.entity(id)?;
+            let target = entity.target().filter(|target| target.since == *since && target.mode == TargetModes::Attack)?;
+            let task = schedule_attack(attacker.clone(), *since, attack_speed(entity));
+            let Some(target_id) = world.find_creature(&target.creature) else {
+                return Some((vec![Box::new(RemoveEntityAttributeCommand {
+                    entity: id,
+                    attribute: Box::new(target.clone()),
+                }) as CommandType], None));
+            };
+            let (position, target_position) = (world.position(id)?, world.position(target_id)?);
+            let Some(weapon) = weapon(entity).filter(|weapon| can_reach(&world, weapon, &position, &target_position)) else {
+                return Some((Vec::new(), Some(task)));
+            };
+            Some((strike(&world, id, &weapon, target_id)?, Some(task)))
+        })() {
+            commands.append(&mut attack_commands);
+            tasks.extend(task);
+        }
+
+        Some((commands, tasks))
+    }
+
+    #[effect(FollowEvent)]
+    fn handle_follow(event: EventType, _: GameAttributesType, world: WorldType) -> EffectResultType {
+        let FollowEvent { follower, since } = event.as_any().downcast_ref::<FollowEvent>().unwrap();
+        let mut commands = Vec::new();
+        let mut tasks = Vec::new();
+        let world = world.lock().unwrap();
+
+        if let Some((command, task)) = (|| {
+            let id = world.find_creature(follower)?;
+            let entity = world.entity(id)?;
+            let target = entity.target().filter(|target| target.since == *since && target.mode == TargetModes::Follow)?;
+            let task = schedule_follow(follower.clone(), *since, step_duration(entity));
+            let Some(target_id) = world.find_creature(&target.creature) else {
+                return Some((Some(Box::new(RemoveEntityAttributeCommand {
+                    entity: id,
+                    attribute: Box::new(target.clone()),
+                }) as CommandType), None));
+            };
+            let (position, target_position) = (world.position(id)?, world.position(target_id)?);
+            if geometry::is_adjacent(&position, &target_position) {
+                return Some((None, Some(task)));
+            }
+            let Some(direction) = Direction::towards(position.clone(), target_position) else {
+                return Some((None, Some(task)));
+            };
+            Some((Some(Box::new(EmitEventCommand(Arc::new(MoveEvent {
+                from: position.clone().stack_pos(None),
+                to: direction.apply_to_position(position).stack_pos(None),
+                creature: follower.clone(),
+            }))) as CommandType), Some(task)))
+        })() {
+            commands.extend(command);
+            tasks.extend(task);
+        }
+
+        Some((commands, tasks))
+    }
+
+    #[effect(DeathEvent)]
+    fn handle_death(event: EventType, _: GameAttributesType, world: WorldType) -> EffectResultType {
+        println!("Handle death");
+        let DeathEvent { entity: id } = event.as_any().downcast_ref::<DeathEvent>().unwrap();
+        let mut commands = Vec::new();
+        let world = world.lock().unwrap();
+
+        if let Some(mut death_commands) = (|| {
+            let entity = world.entity(*id)?;
+            let position = world.position(*id)?;
+            entity.player()?;
+            let player = entity.creature_id()?;
+            let Health { max, .. } = entity.health()?;
+            let mut commands = Vec::new();
+
+            // nobody keeps attacking or following the dead
+            commands.append(&mut untarget(&world, player));
+            commands.append(&mut conditions::clear(*id, entity));
+            commands.push(Box::new(SetEntityAttributeCommand {
+                entity: *id,
+                attribute: Box::new(Health { value: *max, max: *max }),
+            }) as CommandType);
+            commands.push(Box::new(MoveEntityCommand {
+                entity: *id,
+                to: TEMPLE,
+            }) as CommandType);
+            commands.push(Box::new(AddEntityCommand {
+                position: position.stack_pos(None),
+                entity: entity![Item(Items::DeadHuman.into())],
+            }) as CommandType);
+            commands.push(Box::new(SendTextCommand {
+                player: player.clone(),
+                class: MessageClasses::EventAdvance,
+                text: DEAD.into(),
+            }) as CommandType);
+            Some(commands)
+        })() {
+            commands.append(&mut death_commands);
+        }
+
+        Some((commands, Vec::new()))
+    }
+}
@@ -0,0 +1,70 @@
+use crate::prelude::*;
+use skyless_core::prelude::*;
+use std::time::Duration;
+
+pub const SORRY: &str = "Sorry, not possible.";
+
+#[command]
+pub struct SendTextCommand {
+    pub player: CreatureId,
+    pub class: MessageClasses,
+    pub text: String,
+}
+
+impl SendTextCommand {
+    pub fn cancel(player: CreatureId, text: &str) -> Self {
+        Self {
+            player,
+            class: MessageClasses::StatusSmall,
+            text: text.into(),
+        }
+    }
+
+    pub fn sorry(player: CreatureId) -> Self {
+        Self::cancel(player, SORRY)
+    }
+}
+
+#[command]
+pub struct ShowEffectCommand {
+    pub position: Position,
+    pub effect: VisualEffects,
+}
+
+#[command]
+pub struct AddSkillTriesCommand {
+    pub player: CreatureId,
+    pub skill: SkillType,
+    pub tries: u64,
+}
+
+#[command]
+pub struct AddExperienceCommand {
+    pub player: CreatureId,
+    pub experience: u64,
+}
+
+#[command]
+pub struct AddManaSpentCommand {
+    pub player: CreatureId,
+    pub mana: u64,
+}
+
+#[command]
+pub struct AddConditionCommand {
+    pub player: CreatureId,
+    pub condition: ConditionType,
+    pub duration: Duration,
+    pub damage: u16,
+}
+
+#[command]
+pub struct RemoveConditionCommand {
+    pub player: CreatureId,
+    pub condition: ConditionType,
+}
+
+#[command]
+pub struct RegisterSpellCommand {
+    pub spell: Spell,
+}
@@ -0,0 +1,161 @@
+use crate::{combat, prelude::*};
+use skyless_core::prelude::*;
+use std::time::Instant;
+use tokio::time;
+
+#[event]
+pub struct ConditionEvent {
+    pub player: CreatureId,
+    pub condition: ConditionType,
+    pub until: Instant,
+}
+
+fn schedule(player: CreatureId, condition: ConditionType, until: Instant) -> TaskType {
+    let at = match condition.tick() {
+        Some((interval, ..)) => (Instant::now() + interval).min(until),
+        None => until,
+    };
+    Box::pin(stream! {
+        time::sleep_until(at.into()).await;
+        yield Some(Arc::new(ConditionEvent { player, condition, until }) as EventType);
+    })
+}
+
+/// Drops every condition, giving back the speed they took or added.
+pub fn clear(id: EntityId, entity: &Entity) -> CommandsType {
+    let Some(conditions) = entity.conditions() else {
+        return Vec::new();
+    };
+    let mut commands = Vec::new();
+    let change: i32 = conditions.0.values().map(|condition| condition.speed).sum();
+    if let Some(Speed(speed)) = entity.speed().filter(|_| change != 0) {
+        commands.push(Box::new(SetEntityAttributeCommand {
+            entity: id,
+            attribute: Box::new(Speed((i32::from(*speed) - change) as u16)),
+        }) as CommandType);
+    }
+    if conditions.has(ConditionType::Light) {
+        commands.push(Box::new(SetEntityAttributeCommand {
+            entity: id,
+            attribute: Box::new(LightInfo::NONE),
+        }) as CommandType);
+    }
+    commands.push(Box::new(SetEntityAttributeCommand {
+        entity: id,
+        attribute: Box::new(Conditions(HashMap::new())),
+    }) as CommandType);
+    commands
+}
+
+fn remove(id: EntityId, entity: &Entity, condition: ConditionType) -> Option<CommandsType> {
+    let mut conditions = entity.conditions()?.clone();
+    let removed = conditions.0.remove(&condition)?;
+    let mut commands = Vec::new();
+    if removed.speed != 0 {
+        let Speed(speed) = entity.speed()?;
+        commands.push(Box::new(SetEntityAttributeCommand {
+            entity: id,
+            attribute: Box::new(Speed((i32::from(*speed) - removed.speed) as u16)),
+        }) as CommandType);
+    }
+    if condition == ConditionType::Light {
+        commands.push(Box::new(SetEntityAttributeCommand {
+            entity: id,
+            attribute: Box::new(LightInfo::NONE),
+        }) as CommandType);
+    }
+    commands.push(Box::new(SetEntityAttributeCommand {
+        entity: id,
+        attribute: Box::new(conditions),
+    }) as CommandType);
+    Some(commands)
+}
+
+system! {
+    #[handler(AddConditionCommand)]
+    fn handle_add_condition(command: CommandType, _: GameAttributesType, world: WorldType) -> EffectResultType {
+        let AddConditionCommand { player, condition, duration, damage } = command.as_any().downcast_ref::<AddConditionCommand>().unwrap();
+        let mut commands = Vec::new();
+        let mut tasks = Vec::new();
+        let world = world.lock().unwrap();
+
+        if let Some((mut condition_commands, task)) = (|| {
+            let id = world.find_creature(player)?;
+            let entity = world.entity(id)?;
+            let mut conditions = entity.conditions().cloned().unwrap_or_else(|| Conditions(HashMap::new()));
+            let mut speed = entity.speed().map_or(0, |Speed(speed)| i32::from(*speed));
+
+            // refreshing a condition or replacing the one it excludes undoes their speed change first
+            for replaced in [Some(*condition), condition.excludes()].into_iter().flatten() {
+                if let Some(old) = conditions.0.remove(&replaced) {
+                    speed -= old.speed;
+                }
+            }
+            let change = condition.speed_change(speed.max(0) as u16);
+            let until = Instant::now() + *duration;
+            conditions.0.insert(*condition, Condition { until, damage: *damage, speed: change });
+
+            let mut commands = Vec::new();
+            if *condition == ConditionType::Light {
+                commands.push(Box::new(SetEntityAttributeCommand {
+                    entity: id,
+                    attribute: Box::new(LightInfo::TORCH),
+                }) as CommandType);
+            }
+            if entity.speed().is_some_and(|Speed(current)| i32::from(*current) != speed + change) {
+                commands.push(Box::new(SetEntityAttributeCommand {
+                    entity: id,
+                    attribute: Box::new(Speed((speed + change).max(0) as u16)),
+                }) as CommandType);
+            }
+            commands.push(Box::new(SetEntityAttributeCommand {
+                entity: id,
+                attribute: Box::new(conditions),
+            }) as CommandType);
+            Some((commands, schedule(player.clone(), *condition, until)))
+        })() {
+            commands.append(&mut condition_commands);
+            tasks.push(task);
+        }
+
+        Some((commands, tasks))
+    }
+
+    #[handler(RemoveConditionCommand)]
+    fn handle_remove_condition(command: CommandType, _: GameAttributesType, world: WorldType) -> EffectResultType {
+        let RemoveConditionCommand { player, condition } = command.as_any().downcast_ref::<RemoveConditionCommand>().unwrap();
+        let world = world.lock().unwrap();
+        let commands = world
+            .find_creature(player)
+            .and_then(|id| remove(id, world.entity(id)?, *condition))
+            .unwrap_or_default();
+        Some((commands, Vec::new()))
+    }
+
+    #[effect(ConditionEvent)]
+    fn handle_condition(event: EventType, _: GameAttributesType, world: WorldType) -> EffectResultType {
+        let ConditionEvent { player, condition, until } = event.as_any().downcast_ref::<ConditionEvent>().unwrap();
+        let mut commands = Vec::new();
+        let mut tasks = Vec::new();
+        let world = world.lock().unwrap();
+
+        if let Some((mut condition_commands, task)) = (|| {
+            let id = world.find_creature(player)?;
+            let entity = world.entity(id)?;
+            let active = entity.conditions()?.0.get(condition).filter(|active| active.until == *until)?;
+            if Instant::now() >= *until {
+                return Some((remove(id, entity, *condition)?, None));
+            }
+            let mut commands = Vec::new();
+            if let Some((_, effect, color)) = condition.tick().filter(|_| active.damage > 0) {
+                commands.append(&mut combat::damage(&world, id, active.damage, effect, color));
+            }
+            Some((commands, Some(schedule(player.clone(), *condition, *until))))
+        })() {
+            commands.append(&mut condition_commands);
+            tasks.extend(task);
+        }
+
+        Some((commands, tasks))
+    }
+}
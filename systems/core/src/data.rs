@@ -0,0 +1,34 @@
+use serde::de::DeserializeOwned;
+use std::{fs, path::Path};
+
+/// Reads the TOML data file at `path`, telling what went wrong when it cannot.
+pub fn load<T: DeserializeOwned>(path: impl AsRef<Path>) -> Option<T> {
+    let path = path.as_ref();
+    let data = fs::read_to_string(path)
+        .map_err(|error| error.to_string())
+        .and_then(|content| toml::from_str::<T>(&content).map_err(|error| error.to_string()));
+    match data {
+        Ok(data) => Some(data),
+        Err(error) => {
+            println!("Could not load {}: {}", path.display(), error);
+            None
+        }
+    }
+}
+
+/// Reads every TOML data file in the directory at `path`, skipping the broken ones.
+pub fn load_all<T: DeserializeOwned>(path: impl AsRef<Path>) -> Vec<T> {
+    let path = path.as_ref();
+    let Ok(entries) = fs::read_dir(path) else {
+        println!("Could not read {}", path.display());
+        return Vec::new();
+    };
+    entries
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "toml")
+        })
+        .filter_map(load)
+        .collect()
+}
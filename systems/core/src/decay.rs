@@ -0,0 +1,96 @@
+use crate::prelude::*;
+use skyless_core::{prelude::*, World};
+use std::time::SystemTime;
+use tokio::time;
+
+#[event]
+pub struct DecayEvent {
+    pub entity: EntityId,
+    pub until: SystemTime,
+}
+
+fn schedule(entity: EntityId, decay: &Decay) -> TaskType {
+    let (until, remaining) = (decay.until, decay.remaining());
+    Box::pin(stream! {
+        time::sleep(remaining).await;
+        yield Some(Arc::new(DecayEvent { entity, until }) as EventType);
+    })
+}
+
+/// Starts the timer of the item, `resume` keeps the time an item coming into the world had left.
+fn start(world: &World, id: EntityId, resume: bool) -> Option<(CommandsType, Vec<TaskType>)> {
+    let entity = world.entity(id)?;
+    let item = Items::try_from(entity.item()?.0).ok();
+    match item.and_then(|item| item.decay()) {
+        Some((_, duration)) => {
+            if let Some(decay) = entity.decay().filter(|_| resume) {
+                return Some((Vec::new(), vec![schedule(id, decay)]));
+            }
+            let decay = Decay::after(duration);
+            Some((
+                vec![Box::new(SetEntityAttributeCommand {
+                    entity: id,
+                    attribute: Box::new(decay.clone()),
+                }) as CommandType],
+                vec![schedule(id, &decay)],
+            ))
+        }
+        None => {
+            let decay = entity.decay()?;
+            Some((
+                vec![Box::new(RemoveEntityAttributeCommand {
+                    entity: id,
+                    attribute: Box::new(decay.clone()),
+                }) as CommandType],
+                Vec::new(),
+            ))
+        }
+    }
+}
+
+system! {
+    // moved items keep their id and so their timer, items put back into the world resume theirs
+    #[effect(AddedEntityEvent)]
+    fn handle_decay_added_entity(event: EventType, _: GameAttributesType, world: WorldType) -> EffectResultType {
+        let AddedEntityEvent { entity } = event.as_any().downcast_ref::<AddedEntityEvent>().unwrap();
+        let world = world.lock().unwrap();
+        start(&world, *entity, true).or(Some((Vec::new(), Vec::new())))
+    }
+
+    #[effect(ChangedEntityEvent)]
+    fn handle_decay_changed_entity(event: EventType, _: GameAttributesType, world: WorldType) -> EffectResultType {
+        let ChangedEntityEvent { entity, attribute_name } = event.as_any().downcast_ref::<ChangedEntityEvent>().unwrap();
+        if attribute_name.as_str() != "item" {
+            return Some((Vec::new(), Vec::new()));
+        }
+        let world = world.lock().unwrap();
+        start(&world, *entity, false).or(Some((Vec::new(), Vec::new())))
+    }
+
+    #[effect(DecayEvent)]
+    fn handle_decay(event: EventType, _: GameAttributesType, world: WorldType) -> EffectResultType {
+        let DecayEvent { entity: id, until } = event.as_any().downcast_ref::<DecayEvent>().unwrap();
+        let world = world.lock().unwrap();
+        let mut commands = Vec::new();
+        if let Some(command) = (|| {
+            let entity = world.entity(*id)?;
+            if entity.decay()?.until != *until {
+                return None;
+            }
+            let item = Items::try_from(entity.item()?.0).ok()?;
+            let command = match item.decay()? {
+                (Some(next), _) => Box::new(SetEntityAttributeCommand {
+                    entity: *id,
+                    attribute: Box::new(Item(next.into())),
+                }) as CommandType,
+                (None, _) => Box::new(RemoveEntityCommand {
+                    entity: *id,
+                }) as CommandType,
+            };
+            Some(command)
+        })() {
+            commands.push(command);
+        }
+        Some((commands, Vec::new()))
+    }
+}
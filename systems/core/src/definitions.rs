@@ -1,6 +1,14 @@
-use num_enum::IntoPrimitive;
+use super::attributes::Outfit;
+use super::geometry::Areas;
+use super::Items;
+use indexmap::IndexMap;
+use num_enum::{IntoPrimitive, TryFromPrimitive};
+use serde::Deserialize;
+use skyless_core::{CreatureId, Position};
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum SkillType {
     Fist = 0,
     Club = 1,
@@ -11,8 +19,83 @@ pub enum SkillType {
     Fishing = 6,
 }
 
+impl SkillType {
+    pub const ALL: [Self; 7] = [
+        Self::Fist,
+        Self::Club,
+        Self::Sword,
+        Self::Axe,
+        Self::Distance,
+        Self::Shield,
+        Self::Fishing,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Fist => "fist fighting",
+            Self::Club => "club fighting",
+            Self::Sword => "sword fighting",
+            Self::Axe => "axe fighting",
+            Self::Distance => "distance fighting",
+            Self::Shield => "shielding",
+            Self::Fishing => "fishing",
+        }
+    }
+
+    fn base_tries(&self) -> f64 {
+        match self {
+            Self::Fist | Self::Club | Self::Sword | Self::Axe => 50.0,
+            Self::Distance => 30.0,
+            Self::Shield => 100.0,
+            Self::Fishing => 20.0,
+        }
+    }
+
+    fn multiplier(&self) -> f64 {
+        match self {
+            Self::Fishing => 1.1,
+            _ => 1.5,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Skill {
+    pub level: u16,
+    pub tries: u64,
+}
+
+impl Skill {
+    pub const MIN_LEVEL: u16 = 10;
+
+    /// Tries needed to advance from `level - 1` to `level`.
+    pub fn tries_for(skill: SkillType, level: u16) -> u64 {
+        (skill.base_tries() * skill.multiplier().powi(i32::from(level) - 11)) as u64
+    }
+
+    pub fn progress(&self, skill: SkillType) -> u8 {
+        percent(self.tries, Self::tries_for(skill, self.level + 1))
+    }
+}
+
+impl Default for Skill {
+    fn default() -> Self {
+        Self {
+            level: Self::MIN_LEVEL,
+            tries: 0,
+        }
+    }
+}
+
+pub fn percent(value: u64, required: u64) -> u8 {
+    if required == 0 {
+        return 0;
+    }
+    (value.saturating_mul(100) / required).min(100) as u8
+}
+
 #[repr(u8)]
-#[derive(Debug, Clone, IntoPrimitive)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, IntoPrimitive, TryFromPrimitive)]
 pub enum InventorySlot {
     // Wherever = 0,
     Head = 1,
@@ -28,7 +111,7 @@ pub enum InventorySlot {
 }
 
 #[repr(u8)]
-#[derive(Debug, Clone, Copy, IntoPrimitive)]
+#[derive(Debug, Clone, Copy, IntoPrimitive, TryFromPrimitive)]
 pub enum Directions {
     North = 0,
     East,
@@ -41,6 +124,436 @@ pub enum Directions {
     None,
 }
 
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, IntoPrimitive, TryFromPrimitive)]
+pub enum SpeakClasses {
+    Say = 1,
+    Whisper,
+    Yell,
+    Private,
+    Channel,
+    RvrChannel,
+    RvrAnswer,
+    RvrContinue,
+    Broadcast,
+    ChannelRed,
+    PrivateRed,
+    ChannelOrange,
+    MonsterSay = 16,
+    MonsterYell,
+}
+
+impl SpeakClasses {
+    /// Horizontal and vertical hearing range and whether it carries to other floors.
+    pub fn range(&self) -> Option<(u16, u16, bool)> {
+        match self {
+            Self::Whisper => Some((1, 1, false)),
+            Self::Say | Self::MonsterSay => Some((8, 6, false)),
+            Self::Yell | Self::MonsterYell => Some((18, 14, true)),
+            _ => None,
+        }
+    }
+
+    pub fn can_hear(&self, speaker: &Position, listener: &Position) -> bool {
+        self.range().is_some_and(|(range_x, range_y, multi_floor)| {
+            speaker.x.abs_diff(listener.x) <= range_x
+                && speaker.y.abs_diff(listener.y) <= range_y
+                && (multi_floor || speaker.z == listener.z)
+        })
+    }
+}
+
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, IntoPrimitive)]
+pub enum MessageClasses {
+    ConsoleYellow = 0x01,
+    ConsoleLightBlue = 0x04,
+    ConsoleOrange = 0x11,
+    Warning = 0x12,
+    EventAdvance = 0x13,
+    EventDefault = 0x14,
+    StatusDefault = 0x15,
+    InfoDescription = 0x16,
+    StatusSmall = 0x17,
+    ConsoleBlue = 0x18,
+    ConsoleRed = 0x19,
+}
+
+#[repr(u16)]
+#[derive(Debug, Clone, Copy, IntoPrimitive)]
+pub enum DefaultChannels {
+    Guild = 0x00,
+    Party = 0x01,
+    GameChat = 0x04,
+    Trade = 0x05,
+    RealLife = 0x06,
+    Help = 0x07,
+}
+
+#[derive(Debug, Clone)]
+pub struct Channel {
+    pub name: String,
+    /// Public channels are listed to everyone, the rest only to their members.
+    pub public: bool,
+    pub members: HashSet<CreatureId>,
+}
+
+impl Channel {
+    pub fn new(name: &str, public: bool) -> Self {
+        Self {
+            name: name.into(),
+            public,
+            members: HashSet::new(),
+        }
+    }
+
+    pub fn is_visible_to(&self, player: &CreatureId) -> bool {
+        self.public || self.members.contains(player)
+    }
+}
+
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, IntoPrimitive, Deserialize)]
+pub enum MagicEffects {
+    DrawBlood = 0,
+    LoseEnergy,
+    Poff,
+    BlockHit,
+    ExplosionArea,
+    ExplosionHit,
+    FireArea,
+    YellowRings,
+    GreenRings,
+    HitArea,
+    Teleport,
+    EnergyHit,
+    MagicBlue,
+    MagicRed,
+    MagicGreen,
+    HitByFire,
+    HitByPoison,
+    MortArea,
+    SoundGreen,
+    SoundRed,
+    PoisonArea,
+    SoundYellow,
+    SoundPurple,
+    SoundBlue,
+    SoundWhite,
+    Bubbles,
+    Craps,
+    GiftWraps,
+    FireworkYellow,
+    FireworkRed,
+    FireworkBlue,
+    Stun,
+    Sleep,
+    WaterCreature,
+    Groundshaker,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ConditionType {
+    Poison,
+    Burning,
+    Energy,
+    Drunk,
+    ManaShield,
+    Paralyze,
+    Haste,
+    InFight,
+    Light,
+}
+
+impl ConditionType {
+    /// Bit of the status icon shown by the client.
+    pub fn icon(&self) -> u8 {
+        match self {
+            Self::Poison => 1 << 0,
+            Self::Burning => 1 << 1,
+            Self::Energy => 1 << 2,
+            Self::Drunk => 1 << 3,
+            Self::ManaShield => 1 << 4,
+            Self::Paralyze => 1 << 5,
+            Self::Haste => 1 << 6,
+            Self::InFight => 1 << 7,
+            Self::Light => 0,
+        }
+    }
+
+    /// How often a damaging condition hurts, with the effect and text color it shows.
+    pub fn tick(&self) -> Option<(Duration, MagicEffects, TextColors)> {
+        match self {
+            Self::Poison => Some((
+                Duration::from_secs(5),
+                MagicEffects::HitByPoison,
+                TextColors::LightGreen,
+            )),
+            Self::Burning => Some((
+                Duration::from_secs(4),
+                MagicEffects::HitByFire,
+                TextColors::Orange,
+            )),
+            Self::Energy => Some((
+                Duration::from_secs(4),
+                MagicEffects::EnergyHit,
+                TextColors::Purple,
+            )),
+            _ => None,
+        }
+    }
+
+    pub fn speed_change(&self, speed: u16) -> i32 {
+        let speed = i32::from(speed);
+        match self {
+            Self::Haste => speed * 3 / 10 - 24,
+            Self::Paralyze => -(speed * 6 / 10),
+            _ => 0,
+        }
+    }
+
+    /// Conditions that cannot be active at the same time as this one.
+    pub fn excludes(&self) -> Option<Self> {
+        match self {
+            Self::Haste => Some(Self::Paralyze),
+            Self::Paralyze => Some(Self::Haste),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Condition {
+    pub until: Instant,
+    pub damage: u16,
+    pub speed: i32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SpellTriggers {
+    Words(String),
+    Rune(Items),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum SpellAreas {
+    /// Only the target tile, which has to hold a creature.
+    Creature,
+    Area(Areas),
+}
+
+impl SpellAreas {
+    pub fn positions(&self, center: &Position, direction: Directions) -> Vec<Position> {
+        match self {
+            Self::Creature => vec![center.clone()],
+            Self::Area(area) => area.positions(center, direction),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum SpellActions {
+    Heal {
+        min: u16,
+        max: u16,
+    },
+    Damage {
+        min: u16,
+        max: u16,
+        effect: MagicEffects,
+        color: TextColors,
+    },
+    Condition {
+        condition: ConditionType,
+        duration: Duration,
+        damage: u16,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct Spell {
+    pub name: String,
+    pub trigger: SpellTriggers,
+    pub level: u16,
+    pub magic_level: u16,
+    pub mana: u16,
+    pub soul: u8,
+    pub cooldown: Duration,
+    pub area: SpellAreas,
+    pub effect: MagicEffects,
+    pub shoot: Option<ShootTypes>,
+    pub action: SpellActions,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TargetModes {
+    Attack,
+    Follow,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AiStates {
+    /// No player around, the monster does nothing at all.
+    Idle,
+    /// Players are around but none can be attacked.
+    Wander,
+    Chase,
+    /// Stays as far away from the target as its attacks reach.
+    KeepDistance,
+    Flee,
+}
+
+/// One way a monster hurts its target, `range` 1 being a melee attack.
+#[derive(Debug, Clone, Deserialize)]
+pub struct MonsterAttack {
+    #[serde(default = "MonsterAttack::melee_range")]
+    pub range: u16,
+    pub min: u16,
+    pub max: u16,
+    pub shoot: Option<ShootTypes>,
+    pub effect: Option<MagicEffects>,
+}
+
+impl MonsterAttack {
+    fn melee_range() -> u16 {
+        1
+    }
+}
+
+/// Item dropped into the corpse with a `chance` in percent.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Loot {
+    pub item: u16,
+    pub chance: u8,
+    #[serde(default = "Loot::single")]
+    pub count: u8,
+}
+
+impl Loot {
+    fn single() -> u8 {
+        1
+    }
+}
+
+/// Monster type as described in its data file.
+#[derive(Debug, Clone, Deserialize)]
+pub struct MonsterType {
+    pub name: String,
+    pub outfit: Outfit,
+    pub health: u16,
+    pub speed: u16,
+    pub experience: u64,
+    pub corpse: u16,
+    #[serde(default)]
+    pub defense: u16,
+    /// Health at which the monster runs away from its target.
+    #[serde(default)]
+    pub flee_health: u16,
+    #[serde(default)]
+    pub attacks: Vec<MonsterAttack>,
+    #[serde(default)]
+    pub loot: Vec<Loot>,
+}
+
+/// Area monsters of one type are spawned in, as described in the spawns data file.
+#[derive(Debug, Clone, Deserialize)]
+pub struct SpawnArea {
+    pub monster: String,
+    pub center: (u16, u16, u8),
+    #[serde(default)]
+    pub radius: u16,
+    #[serde(default = "SpawnArea::single")]
+    pub amount: u8,
+    /// Seconds until a killed monster comes back.
+    pub interval: u64,
+}
+
+impl SpawnArea {
+    fn single() -> u8 {
+        1
+    }
+}
+
+/// NPC as described in its data file, `{player}` in what it says is replaced by the name of whom it talks to.
+#[derive(Debug, Clone, Deserialize)]
+pub struct NpcType {
+    pub name: String,
+    pub outfit: Outfit,
+    pub position: (u16, u16, u8),
+    /// Distance the NPC walks around from its position.
+    #[serde(default)]
+    pub radius: u16,
+    #[serde(default = "NpcType::speed")]
+    pub speed: u16,
+    pub greeting: String,
+    pub farewell: String,
+    /// Answers to keywords said during a conversation, in the order of the data file.
+    #[serde(default)]
+    pub keywords: IndexMap<String, String>,
+    /// What is offered in the trade window, opened when the player asks for a trade.
+    #[serde(default)]
+    pub shop: Vec<ShopOffer>,
+}
+
+/// Item traded by an NPC, prices are in gold and zero when it is not bought or sold that way.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ShopOffer {
+    pub item: u16,
+    pub name: String,
+    #[serde(default)]
+    pub buy: u32,
+    #[serde(default)]
+    pub sell: u32,
+}
+
+impl NpcType {
+    fn speed() -> u16 {
+        100
+    }
+}
+
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, IntoPrimitive, Deserialize)]
+pub enum ShootTypes {
+    Spear = 0,
+    Bolt,
+    Arrow,
+    Fire,
+    Energy,
+    PoisonArrow,
+    BurstArrow,
+    ThrowingStar,
+    ThrowingKnife,
+    SmallStone,
+    Death,
+    LargeRock,
+    Snowball,
+    PowerBolt,
+    Poison,
+}
+
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, IntoPrimitive)]
+pub enum TextColors {
+    Blue = 5,
+    LightGreen = 30,
+    LightBlue = 35,
+    Purple = 83,
+    LightGrey = 129,
+    DarkRed = 144,
+    Red = 180,
+    Orange = 198,
+    Yellow = 210,
+    WhiteExperience = 215,
+}
+
+#[derive(Debug, Clone)]
+pub enum VisualEffects {
+    Magic(MagicEffects),
+    Distance { to: Position, shoot: ShootTypes },
+    AnimatedText { color: TextColors, text: String },
+}
+
 #[repr(u8)]
 #[derive(Debug, Clone, Copy, IntoPrimitive)]
 pub enum Skulls {
@@ -62,3 +575,23 @@ pub enum PartyShields {
     Blue = 3,
     Yellow = 4,
 }
+
+#[derive(Debug, Clone)]
+pub enum LinkEffect {
+    Transform { from: Items, to: Items },
+    Create(Items),
+    Remove(Items),
+}
+
+#[derive(Debug, Clone)]
+pub struct LinkTarget {
+    pub position: Position,
+    pub effect: LinkEffect,
+}
+
+#[derive(Debug, Clone)]
+pub enum Doors {
+    Regular,
+    Level(u16),
+    Quest(u32),
+}
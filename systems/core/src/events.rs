@@ -5,23 +5,71 @@ use skyless_core::prelude::*;
 pub struct MoveEvent {
     pub from: Position,
     pub to: Position,
-    pub player: Option<Player>, // TODO: use generic 'Id' attribute instead?
+    pub creature: CreatureId,
+}
+
+#[event]
+pub struct TeleportEvent {
+    pub entity: EntityId,
+    pub to: Position,
 }
 
 #[event]
 pub struct CollisionEvent {
-    pub first: Position,
-    pub second: Position,
+    pub first: EntityId,
+    pub second: EntityId,
 }
 
 #[event]
 pub struct SeparationEvent {
-    pub first: Position,
-    pub second: Position,
+    pub first: EntityId,
+    pub second: EntityId,
 }
 
 #[event]
 pub struct UseEvent {
+    pub player: Option<CreatureId>,
     pub source: Option<Position>,
-    pub target: Position,
+    pub target: EntityId,
+}
+
+#[event]
+pub struct SpeakEvent {
+    pub player: Option<CreatureId>,
+    pub position: Position,
+    pub class: SpeakClasses,
+    pub text: String,
+}
+
+#[event]
+pub struct HitEvent {
+    pub attacker: CreatureId,
+    pub target: CreatureId,
+    pub damage: u16,
+}
+
+#[event]
+pub struct DeathEvent {
+    pub entity: EntityId,
+}
+
+/// Player buying `amount` of the item from the NPC whose trade window is open.
+#[event]
+pub struct BuyEvent {
+    pub player: CreatureId,
+    pub item: u16,
+    pub amount: u8,
+}
+
+#[event]
+pub struct SellEvent {
+    pub player: CreatureId,
+    pub item: u16,
+    pub amount: u8,
+}
+
+/// Player leaving the game, either asked for or because the connection was lost.
+#[event]
+pub struct LogoutEvent {
+    pub player: CreatureId,
 }
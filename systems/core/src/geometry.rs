@@ -0,0 +1,218 @@
+use crate::prelude::*;
+use skyless_core::{prelude::*, World};
+
+/// Floors go from 0 high up in the sky, through 7 at the ground level, down to 15.
+pub const DEEPEST_FLOOR: u8 = 15;
+
+/// Position `x` and `y` tiles away on the same floor, if it is still on the map.
+pub fn offset(position: &Position, x: i32, y: i32) -> Option<Position> {
+    let x = u16::try_from(i32::from(position.x) + x).ok()?;
+    let y = u16::try_from(i32::from(position.y) + y).ok()?;
+    Some(position.clone().x(x).y(y).stack_pos(None))
+}
+
+/// Chebyshev distance, the number of steps when diagonal steps are allowed.
+pub fn distance(first: &Position, second: &Position) -> u16 {
+    let (x, y, _) = first.diff(second.clone());
+    x.unsigned_abs().max(y.unsigned_abs()) as u16
+}
+
+/// Manhattan distance, the number of steps when only straight steps are allowed.
+pub fn manhattan_distance(first: &Position, second: &Position) -> u16 {
+    let (x, y, _) = first.diff(second.clone());
+    (x.unsigned_abs() + y.unsigned_abs()) as u16
+}
+
+pub fn is_adjacent(first: &Position, second: &Position) -> bool {
+    first.z == second.z && distance(first, second) <= 1
+}
+
+/// Every position on the floor of `center` at most `range` steps away from it, `center` included.
+pub fn in_range(center: &Position, range: u16) -> impl Iterator<Item = Position> + '_ {
+    let range = i32::from(range);
+    (-range..=range).flat_map(move |y| (-range..=range).filter_map(move |x| offset(center, x, y)))
+}
+
+/// Tiles crossed by the straight line from `from` to `to`, both ends included.
+pub fn line(from: &Position, to: &Position) -> Vec<Position> {
+    let (dx, dy, _) = from.diff(to.clone());
+    let (step_x, step_y) = (dx.signum(), dy.signum());
+    let (dx, dy) = (dx.abs(), -dy.abs());
+    let (mut x, mut y, mut error) = (0, 0, dx + dy);
+    let mut positions = Vec::new();
+    loop {
+        positions.extend(offset(from, x, y));
+        if x == step_x * dx && y == step_y * -dy {
+            return positions;
+        }
+        let doubled = 2 * error;
+        if doubled >= dy {
+            error += dy;
+            x += step_x;
+        }
+        if doubled <= dx {
+            error += dx;
+            y += step_y;
+        }
+    }
+}
+
+/// Whether nothing blocking stands between `from` and `to`, the ends themselves are not checked.
+pub fn is_sight_clear(world: &World, from: &Position, to: &Position) -> bool {
+    if from.z != to.z {
+        return false;
+    }
+    let positions = line(from, to);
+    positions
+        .iter()
+        .skip(1)
+        .take(positions.len().saturating_sub(2))
+        .all(|position| {
+            !world
+                .entities(position)
+                .any(|(_, entity)| entity.item().is_some_and(Item::is_blocking))
+        })
+}
+
+/// Whether a creature can step on the tile, an existing one nobody stands on and nothing blocks.
+pub fn is_walkable(world: &World, position: &Position) -> bool {
+    world.tile(position).is_some()
+        && !world.entities(position).any(|(_, entity)| {
+            entity.creature_id().is_some() || entity.item().is_some_and(Item::is_blocking)
+        })
+}
+
+/// The position itself when it is walkable, the first walkable one next to it otherwise.
+pub fn walkable_near(world: &World, position: &Position) -> Option<Position> {
+    std::iter::once(position.clone().stack_pos(None))
+        .chain(in_range(position, 1))
+        .find(|position| is_walkable(world, position))
+}
+
+/// Shapes laid out around a center, the directional ones pointing north before being rotated.
+#[derive(Debug, Clone, Copy)]
+pub enum Areas {
+    Circle(u16),
+    /// Straight line of the given length in front of the center.
+    Beam(u16),
+    /// Cone of the given length in front of the center, widening every other row.
+    Wave(u16),
+}
+
+impl Areas {
+    fn offsets(&self) -> Vec<(i32, i32)> {
+        match *self {
+            Self::Circle(radius) => {
+                let radius = i32::from(radius);
+                (-radius..=radius)
+                    .flat_map(|y| (-radius..=radius).map(move |x| (x, y)))
+                    .filter(|(x, y)| x * x + y * y <= radius * radius)
+                    .collect()
+            }
+            Self::Beam(length) => (1..=i32::from(length)).map(|row| (0, -row)).collect(),
+            Self::Wave(length) => (1..=i32::from(length))
+                .flat_map(|row| (-row / 2..=row / 2).map(move |x| (x, -row)))
+                .collect(),
+        }
+    }
+
+    /// Positions covered when laid out at `center` facing `direction`, diagonals face north or south.
+    pub fn positions(&self, center: &Position, direction: Directions) -> Vec<Position> {
+        self.offsets()
+            .into_iter()
+            .map(|(x, y)| match direction {
+                Directions::East => (-y, x),
+                Directions::South | Directions::SouthWest | Directions::SouthEast => (-x, -y),
+                Directions::West => (y, -x),
+                _ => (x, y),
+            })
+            .filter_map(|(x, y)| offset(center, x, y))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn coordinates(positions: &[Position]) -> Vec<(u16, u16)> {
+        positions
+            .iter()
+            .map(|position| (position.x, position.y))
+            .collect()
+    }
+
+    #[test]
+    fn line_includes_both_ends() {
+        let from = Position(100, 100, 7);
+        for (x, y) in [(105, 102), (95, 103), (100, 94), (102, 100), (97, 97)] {
+            let to = Position(x, y, 7);
+            let positions = line(&from, &to);
+            assert_eq!(positions.first(), Some(&from));
+            assert_eq!(positions.last(), Some(&to));
+            assert_eq!(positions.len() as u16, distance(&from, &to) + 1);
+        }
+        assert_eq!(coordinates(&line(&from, &from)), [(100, 100)]);
+    }
+
+    #[test]
+    fn beam_points_to_the_direction() {
+        let center = Position(100, 100, 7);
+        let beam = Areas::Beam(3);
+        assert_eq!(
+            coordinates(&beam.positions(&center, Directions::North)),
+            [(100, 99), (100, 98), (100, 97)]
+        );
+        assert_eq!(
+            coordinates(&beam.positions(&center, Directions::East)),
+            [(101, 100), (102, 100), (103, 100)]
+        );
+        assert_eq!(
+            coordinates(&beam.positions(&center, Directions::South)),
+            [(100, 101), (100, 102), (100, 103)]
+        );
+        assert_eq!(
+            coordinates(&beam.positions(&center, Directions::West)),
+            [(99, 100), (98, 100), (97, 100)]
+        );
+    }
+
+    #[test]
+    fn wave_widens_in_front_of_the_center() {
+        let center = Position(100, 100, 7);
+        let wave = Areas::Wave(3);
+        let mut north = coordinates(&wave.positions(&center, Directions::North));
+        north.sort();
+        assert_eq!(
+            north,
+            [
+                (99, 97),
+                (99, 98),
+                (100, 97),
+                (100, 98),
+                (100, 99),
+                (101, 97),
+                (101, 98)
+            ]
+        );
+
+        for direction in [
+            Directions::North,
+            Directions::East,
+            Directions::South,
+            Directions::West,
+        ] {
+            let positions = wave.positions(&center, direction);
+            assert_eq!(positions.len(), 7);
+            assert!(positions.iter().all(|position| {
+                let (x, y, _) = center.diff(position.clone());
+                match direction {
+                    Directions::North => y < 0 && x.abs() <= -y / 2,
+                    Directions::East => x > 0 && y.abs() <= x / 2,
+                    Directions::South => y > 0 && x.abs() <= y / 2,
+                    _ => x < 0 && y.abs() <= -x / 2,
+                }
+            }));
+        }
+    }
+}
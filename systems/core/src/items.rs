@@ -1,12 +1,195 @@
+use crate::definitions::{ShootTypes, SkillType};
 use num_enum::{IntoPrimitive, TryFromPrimitive};
+use skyless_core::StackOrder;
+use std::time::Duration;
 
 #[repr(u16)]
-#[derive(Debug, Clone, Copy, IntoPrimitive, TryFromPrimitive)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, IntoPrimitive, TryFromPrimitive)]
 pub enum Items {
     Grass = 106,
     StoneSwitch = 431,
     StoneSwitchActivated = 430,
+    Wall = 1026,
+    LockedDoor = 1209,
+    ClosedDoor = 1210,
+    OpenDoor = 1211,
+    ClosedQuestDoor = 1223,
+    OpenQuestDoor = 1224,
+    ClosedLevelDoor = 1227,
+    OpenLevelDoor = 1228,
+    Bridge = 1284,
+    Stone = 1304,
+    MagicForcefield = 1387,
+    Backpack = 1988,
+    Key = 2087,
+    GoldCoin = 2148,
+    PlatinumCoin = 2152,
+    CrystalCoin = 2160,
+    SuddenDeathRune = 2268,
+    UltimateHealingRune = 2273,
+    GreatFireballRune = 2304,
+    Sword = 2376,
+    Spear = 2389,
+    Crossbow = 2455,
+    Bow = 2456,
+    Bolt = 2543,
+    Arrow = 2544,
     LeverLeft = 2772,
     LeverRight = 2773,
+    DeadTroll = 2806,
+    DeadRat = 2813,
     Splash = 2889,
+    DeadHuman = 3058,
+}
+
+impl Items {
+    /// Most items a single stack can hold.
+    pub const MAX_STACK: u8 = 100;
+
+    pub fn is_blocking(&self) -> bool {
+        matches!(
+            self,
+            Self::Wall
+                | Self::Stone
+                | Self::LockedDoor
+                | Self::ClosedDoor
+                | Self::ClosedQuestDoor
+                | Self::ClosedLevelDoor
+        )
+    }
+
+    /// Where clients stack the item on its tile, walls and doors are drawn over creatures.
+    pub fn stack_order(&self) -> StackOrder {
+        match self {
+            Self::Grass | Self::Bridge | Self::StoneSwitch | Self::StoneSwitchActivated => {
+                StackOrder::Ground
+            }
+            Self::Wall
+            | Self::LockedDoor
+            | Self::ClosedDoor
+            | Self::OpenDoor
+            | Self::ClosedQuestDoor
+            | Self::OpenQuestDoor
+            | Self::ClosedLevelDoor
+            | Self::OpenLevelDoor => StackOrder::Top(2),
+            Self::MagicForcefield => StackOrder::Top(1),
+            _ => StackOrder::Bottom,
+        }
+    }
+
+    /// Item the given one turns into (`None` when it disappears) and after how long.
+    ///
+    /// Activated switches are left out, they are reset along with what they are linked to.
+    pub fn decay(&self) -> Option<(Option<Self>, Duration)> {
+        match self {
+            Self::Splash => Some((None, Duration::from_secs(20))),
+            Self::DeadTroll | Self::DeadRat | Self::DeadHuman => {
+                Some((None, Duration::from_secs(300)))
+            }
+            _ => None,
+        }
+    }
+
+    /// Weight in hundredths of an ounce.
+    pub fn weight(&self) -> u32 {
+        match self {
+            Self::Key => 100,
+            Self::GoldCoin | Self::PlatinumCoin | Self::CrystalCoin => 10,
+            Self::Sword => 3500,
+            Self::Spear => 2000,
+            Self::Crossbow => 4000,
+            Self::Bow => 3100,
+            Self::Bolt => 80,
+            Self::Arrow => 70,
+            _ => 0,
+        }
+    }
+
+    pub fn is_stackable(&self) -> bool {
+        matches!(
+            self,
+            Self::GoldCoin | Self::PlatinumCoin | Self::CrystalCoin | Self::Bolt | Self::Arrow
+        )
+    }
+
+    /// Worth of a single coin, in gold.
+    pub fn value(&self) -> Option<u64> {
+        match self {
+            Self::GoldCoin => Some(1),
+            Self::PlatinumCoin => Some(100),
+            Self::CrystalCoin => Some(10000),
+            _ => None,
+        }
+    }
+
+    /// Skill trained by the weapon (or ammunition) and its attack value.
+    pub fn attack(&self) -> Option<(SkillType, u16)> {
+        match self {
+            Self::Sword => Some((SkillType::Sword, 14)),
+            Self::Spear => Some((SkillType::Distance, 25)),
+            Self::Bolt => Some((SkillType::Distance, 30)),
+            Self::Arrow => Some((SkillType::Distance, 25)),
+            _ => None,
+        }
+    }
+
+    /// How far a distance weapon reaches, in tiles.
+    pub fn range(&self) -> Option<u16> {
+        match self {
+            Self::Spear => Some(4),
+            Self::Crossbow => Some(5),
+            Self::Bow => Some(6),
+            _ => None,
+        }
+    }
+
+    /// Ammunition a launcher needs in the ammo slot, throwables are their own ammunition.
+    pub fn ammo(&self) -> Option<Self> {
+        match self {
+            Self::Crossbow => Some(Self::Bolt),
+            Self::Bow => Some(Self::Arrow),
+            _ => None,
+        }
+    }
+
+    pub fn shoot(&self) -> Option<ShootTypes> {
+        match self {
+            Self::Spear => Some(ShootTypes::Spear),
+            Self::Bolt => Some(ShootTypes::Bolt),
+            Self::Arrow => Some(ShootTypes::Arrow),
+            _ => None,
+        }
+    }
+
+    pub fn defense(&self) -> Option<u16> {
+        match self {
+            Self::Sword => Some(12),
+            _ => None,
+        }
+    }
+
+    pub fn opened(&self) -> Option<Self> {
+        match self {
+            Self::LockedDoor | Self::ClosedDoor => Some(Self::OpenDoor),
+            Self::ClosedQuestDoor => Some(Self::OpenQuestDoor),
+            Self::ClosedLevelDoor => Some(Self::OpenLevelDoor),
+            _ => None,
+        }
+    }
+
+    pub fn closed(&self) -> Option<Self> {
+        match self {
+            Self::OpenDoor => Some(Self::ClosedDoor),
+            Self::OpenQuestDoor => Some(Self::ClosedQuestDoor),
+            Self::OpenLevelDoor => Some(Self::ClosedLevelDoor),
+            _ => None,
+        }
+    }
+
+    pub fn locked(&self) -> Option<Self> {
+        match self {
+            Self::ClosedDoor | Self::OpenDoor => Some(Self::LockedDoor),
+            _ => None,
+        }
+    }
 }
@@ -1,10 +1,24 @@
 #![forbid(unsafe_code)]
 
+pub mod ai;
 pub mod attributes;
+pub mod combat;
+pub mod commands;
+pub mod conditions;
+pub mod data;
+pub mod decay;
 pub mod definitions;
 pub mod events;
+pub mod geometry;
 pub mod map;
+pub mod monsters;
 pub mod network;
+pub mod npcs;
+pub mod persistence;
+pub mod progression;
+pub mod regeneration;
+pub mod shops;
+pub mod spells;
 
 mod actions;
 mod items;
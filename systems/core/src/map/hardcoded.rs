@@ -1,11 +1,13 @@
+use super::stack_order;
 use crate::prelude::*;
 use itertools::Itertools;
 use skyless_core::{prelude::*, World};
+use std::time::Duration;
 
 system! {
     #[effect(SystemsLoadedEvent)]
     fn load_hardcoded_map(_: EventType, _: GameAttributesType, _: WorldType) -> EffectResultType {
-        let mut tiles = HashMap::new();
+        let mut world = World::new().ordered_by(stack_order);
 
         const CENTER: u8 = 128;
         const RANGE: u8 = 3;
@@ -18,20 +20,83 @@ system! {
             let position = Position(x.into(), y.into(), z);
             let mut entities = Vec::new();
             if x == 126 && y == 126 {
-                entities.push(entity![Item(Items::StoneSwitch.into()), Action(Actions::Switch)]);
+                entities.push(entity![
+                    Item(Items::StoneSwitch.into()),
+                    Action(Actions::Switch),
+                    Link {
+                        targets: vec![LinkTarget {
+                            position: Position(126, 130, FLOOR),
+                            effect: LinkEffect::Remove(Items::Stone),
+                        }],
+                        reset: None,
+                    }
+                ]);
             } else {
                 entities.push(entity![Item(Items::Grass.into())]);
                 if x == 130 && y == 126 {
-                    entities.push(entity![Item(Items::LeverLeft.into()), Action(Actions::Lever)]);
+                    entities.push(entity![
+                        Item(Items::LeverLeft.into()),
+                        Action(Actions::Lever),
+                        Link {
+                            targets: vec![LinkTarget {
+                                position: Position(130, 130, FLOOR),
+                                effect: LinkEffect::Transform { from: Items::Wall, to: Items::Bridge },
+                            }],
+                            reset: Some(Duration::from_secs(10)),
+                        }
+                    ]);
+                } else if x == 130 && y == 130 {
+                    entities.push(entity![Item(Items::Wall.into())]);
+                } else if x == 126 && y == 130 {
+                    entities.push(entity![Item(Items::Stone.into())]);
+                } else if x == 128 && y == 126 {
+                    entities.push(entity![
+                        Item(Items::LockedDoor.into()),
+                        Action(Actions::Door),
+                        Door(Doors::Regular),
+                        ActionId(1001)
+                    ]);
+                } else if x == 128 && y == 130 {
+                    entities.push(entity![
+                        Item(Items::ClosedLevelDoor.into()),
+                        Action(Actions::Door),
+                        Door(Doors::Level(1))
+                    ]);
+                } else if x == 125 && y == 125 {
+                    entities.push(entity![
+                        Item(Items::MagicForcefield.into()),
+                        Teleport(Position(136, 136, FLOOR - 1))
+                    ]);
+                } else if x == 127 && y == 129 {
+                    entities.push(entity![Item(Items::UltimateHealingRune.into()), Count(5)]);
+                } else if x == 129 && y == 129 {
+                    entities.push(entity![Item(Items::GreatFireballRune.into()), Count(5)]);
+                } else if x == 131 && y == 128 {
+                    entities.push(entity![
+                        Item(Items::ClosedQuestDoor.into()),
+                        Action(Actions::Door),
+                        Door(Doors::Quest(1))
+                    ]);
                 }
             }
-            let tile = Tile {
-                attributes: HashMap::new(),
-                entities,
-            };
-            tiles.insert(position, tile);
+            world.add_tile(position, entities);
         }
 
-        Some((vec![Box::new(SetWorldCommand(World(tiles)))], Vec::new()))
+        let upper_range = 135..138;
+        let upper_iter = upper_range.clone().cartesian_product(upper_range).map(|(x, y)| (x, y, FLOOR - 1));
+
+        for (x, y, z) in upper_iter {
+            let position = Position(x, y, z);
+            let mut entities = vec![entity![Item(Items::Grass.into())]];
+            if x == 137 && y == 137 {
+                entities.push(entity![
+                    Item(Items::MagicForcefield.into()),
+                    Teleport(Position(128, 129, FLOOR))
+                ]);
+            }
+            world.add_tile(position, entities);
+        }
+
+        Some((vec![Box::new(SetWorldCommand(world))], Vec::new()))
     }
 }
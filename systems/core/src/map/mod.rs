@@ -1 +1,15 @@
+use crate::prelude::*;
+use skyless_core::prelude::*;
+
 pub mod hardcoded;
+
+/// Layer of the entity on its tile, the map is loaded into a world ordered by it.
+pub fn stack_order(entity: &Entity) -> StackOrder {
+    if entity.creature_id().is_some() {
+        return StackOrder::Creature;
+    }
+    entity
+        .item()
+        .and_then(|Item(id)| Items::try_from(*id).ok())
+        .map_or(StackOrder::Bottom, |item| item.stack_order())
+}
@@ -0,0 +1,189 @@
+use crate::{combat, data, geometry, prelude::*};
+use serde::Deserialize;
+use skyless_core::{prelude::*, World};
+use std::time::Duration;
+use tokio::time;
+
+const MONSTERS_PATH: &str = "data/monsters";
+const SPAWNS_PATH: &str = "data/spawns.toml";
+
+#[derive(Deserialize)]
+struct Spawns {
+    #[serde(default)]
+    spawns: Vec<SpawnArea>,
+}
+
+#[event]
+pub struct RespawnEvent {
+    pub monster: String,
+    pub spawn: Spawn,
+}
+
+fn schedule_respawn(monster: String, spawn: Spawn) -> TaskType {
+    Box::pin(stream! {
+        time::sleep(spawn.interval).await;
+        yield Some(Arc::new(RespawnEvent { monster, spawn }) as EventType);
+    })
+}
+
+/// Random tile of the spawn area nobody stands on and nothing blocks, leaving out the `taken` ones.
+fn free_position(world: &World, spawn: &Spawn, taken: &[Position]) -> Option<Position> {
+    let positions: Vec<Position> = geometry::in_range(&spawn.center, spawn.radius)
+        .filter(|position| !taken.contains(position))
+        .filter(|position| geometry::is_walkable(world, position))
+        .collect();
+    fastrand::choice(positions)
+}
+
+fn monster(monster_type: &MonsterType, id: CreatureId, spawn: Spawn) -> Entity {
+    let outfit = monster_type.outfit.clone();
+    let health = Health {
+        value: monster_type.health,
+        max: monster_type.health,
+    };
+    entity![
+        id,
+        Monster(monster_type.name.clone()),
+        Name(monster_type.name.clone()),
+        health,
+        Direction(Directions::South),
+        outfit,
+        LightInfo::NONE,
+        Speed(monster_type.speed),
+        Defense(monster_type.defense),
+        Skull(Skulls::None),
+        PartyShield(PartyShields::None),
+        spawn
+    ]
+}
+
+fn loot(loot: &Loot) -> Entity {
+    let stackable = Items::try_from(loot.item).is_ok_and(|item| item.is_stackable());
+    if stackable && loot.count > 1 {
+        entity![Item(loot.item), Count(fastrand::u8(1..=loot.count))]
+    } else {
+        entity![Item(loot.item)]
+    }
+}
+
+system! {
+    // the map has to be loaded before, monsters can only be placed on existing tiles
+    #[effect(SystemsLoadedEvent)]
+    fn load_monsters(_: EventType, attributes: GameAttributesType, world: WorldType) -> EffectResultType {
+        let monster_types: HashMap<String, MonsterType> = data::load_all::<MonsterType>(MONSTERS_PATH)
+            .into_iter()
+            .map(|monster_type| (monster_type.name.clone(), monster_type))
+            .collect();
+        let mut creature_ids = attributes.lock().unwrap().creature_ids().cloned().unwrap_or_default();
+        let world = world.lock().unwrap();
+        let mut commands = Vec::new();
+        let mut tasks = Vec::new();
+        let mut taken = Vec::new();
+
+        let spawns = data::load::<Spawns>(SPAWNS_PATH).map_or_else(Vec::new, |Spawns { spawns }| spawns);
+        for area in spawns {
+            let Some(monster_type) = monster_types.get(&area.monster) else {
+                println!("Could not spawn unknown monster {}", area.monster);
+                continue;
+            };
+            let (x, y, z) = area.center;
+            let spawn = Spawn {
+                center: Position(x, y, z),
+                radius: area.radius,
+                interval: Duration::from_secs(area.interval),
+            };
+            for _ in 0..area.amount {
+                let Some(position) = free_position(&world, &spawn, &taken) else {
+                    tasks.push(schedule_respawn(area.monster.clone(), spawn.clone()));
+                    continue;
+                };
+                taken.push(position.clone());
+                commands.push(Box::new(AddEntityCommand {
+                    position,
+                    entity: monster(monster_type, creature_ids.allocate(), spawn.clone()),
+                }) as CommandType);
+            }
+        }
+
+        commands.push(Box::new(SetGameAttributeCommand(Box::new(MonsterTypes(monster_types)))) as CommandType);
+        commands.push(Box::new(SetGameAttributeCommand(Box::new(creature_ids))) as CommandType);
+        Some((commands, tasks))
+    }
+
+    #[effect(RespawnEvent)]
+    fn handle_respawn(event: EventType, attributes: GameAttributesType, world: WorldType) -> EffectResultType {
+        let RespawnEvent { monster: name, spawn } = event.as_any().downcast_ref::<RespawnEvent>().unwrap();
+        let mut commands = Vec::new();
+        let mut tasks = Vec::new();
+        let game_attributes = attributes.lock().unwrap();
+        let world = world.lock().unwrap();
+
+        let Some(monster_type) = game_attributes.monster_types().and_then(|MonsterTypes(types)| types.get(name)) else {
+            return Some((commands, tasks));
+        };
+        match free_position(&world, spawn, &[]) {
+            Some(position) => {
+                let mut creature_ids = game_attributes.creature_ids().cloned().unwrap_or_default();
+                let id = creature_ids.allocate();
+                commands.push(Box::new(SetGameAttributeCommand(Box::new(creature_ids))) as CommandType);
+                commands.push(Box::new(ShowEffectCommand {
+                    position: position.clone(),
+                    effect: VisualEffects::Magic(MagicEffects::Teleport),
+                }) as CommandType);
+                commands.push(Box::new(AddEntityCommand {
+                    position,
+                    entity: monster(monster_type, id, spawn.clone()),
+                }) as CommandType);
+            }
+            // the spawn area is crowded, it is tried again after another interval
+            None => tasks.push(schedule_respawn(name.clone(), spawn.clone())),
+        }
+
+        Some((commands, tasks))
+    }
+
+    #[effect(DeathEvent)]
+    fn handle_monster_death(event: EventType, attributes: GameAttributesType, world: WorldType) -> EffectResultType {
+        let DeathEvent { entity: id } = event.as_any().downcast_ref::<DeathEvent>().unwrap();
+        let mut commands = Vec::new();
+        let mut tasks = Vec::new();
+        let game_attributes = attributes.lock().unwrap();
+        let world = world.lock().unwrap();
+
+        if let Some((mut death_commands, task)) = (|| {
+            let entity = world.entity(*id)?;
+            let position = world.position(*id)?.stack_pos(None);
+            let Monster(name) = entity.monster()?;
+            let creature = entity.creature_id()?;
+            let monster_type = game_attributes.monster_types()?.0.get(name)?;
+            let mut commands = Vec::new();
+
+            // the experience is split between the players by the damage each of them dealt
+            let damages = entity.damages().map(|Damages(damages)| damages.clone()).unwrap_or_default();
+            let total = damages.values().map(|damage| u64::from(*damage)).sum::<u64>();
+            for (player, damage) in damages {
+                let is_player = world.find_creature(&player).and_then(|attacker| world.entity(attacker)?.player()).is_some();
+                if is_player && total > 0 {
+                    let experience = monster_type.experience * u64::from(damage) / total;
+                    commands.push(Box::new(AddExperienceCommand { player, experience }) as CommandType);
+                }
+            }
+
+            let dropped: Vec<Entity> = monster_type.loot.iter().filter(|dropped| fastrand::u8(0..100) < dropped.chance).map(loot).collect();
+            let (corpse_name, container) = (Name(format!("dead {}", name.to_lowercase())), Container(dropped));
+            commands.append(&mut combat::untarget(&world, creature));
+            commands.push(Box::new(RemoveEntityCommand { entity: *id }) as CommandType);
+            commands.push(Box::new(AddEntityCommand {
+                position: position.clone(),
+                entity: entity![Item(monster_type.corpse), corpse_name, container],
+            }) as CommandType);
+            let task = entity.spawn().map(|spawn| schedule_respawn(name.clone(), spawn.clone()));
+            Some((commands, task))
+        })() {
+            commands.append(&mut death_commands);
+            tasks.extend(task);
+        }
+
+        Some((commands, tasks))
+    }
+}
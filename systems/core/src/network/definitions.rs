@@ -15,21 +15,41 @@ pub struct Packet(pub Payload);
 pub enum ClientOpcodes {
     Login = 0x01,
     GameLogin = 0x0A,
+    Logout = 0x14,
     Ping = 0x1E,
     PingBack = 0x1D,
     MoveNorth = 0x65,
     MoveEast = 0x66,
     MoveSouth = 0x67,
     MoveWest = 0x68,
+    Purchase = 0x7A,
+    Sell = 0x7B,
+    CloseShop = 0x7C,
     UseItem = 0x82,
-    // UseItemOn = 0x83,
+    UseItemOn = 0x83,
+    Talk = 0x96,
+    RequestChannels = 0x97,
+    OpenChannel = 0x98,
+    CloseChannel = 0x99,
+    OpenPrivateChannel = 0x9A,
+    Attack = 0xA1,
+    Follow = 0xA2,
+    Cancel = 0xBE,
 }
 
+// the login server answers with its own opcodes, some of them are taken by game ones
 #[repr(u8)]
 #[derive(IntoPrimitive)]
-pub enum ServerOpcodes {
+pub enum LoginServerOpcodes {
+    Error = 0x0A,
     CharacterList = 0x14,
+}
+
+#[repr(u8)]
+#[derive(IntoPrimitive)]
+pub enum ServerOpcodes {
     GameLogin = 0x0A,
+    Disconnect = 0x14,
     Ping = 0x1E,
     MapFull = 0x64,
     MapNorth = 0x65,
@@ -38,16 +58,35 @@ pub enum ServerOpcodes {
     MapWest = 0x68,
     InventoryItem = 0x78,
     InventoryEmpty = 0x79,
+    OpenedShop = 0x7A,
+    PlayerGoods = 0x7B,
+    ClosedShop = 0x7C,
     Stats = 0xA0,
     Skills = 0xA1,
     WorldLight = 0x82,
+    MagicEffect = 0x83,
+    AnimatedText = 0x84,
+    DistanceShot = 0x85,
+    Square = 0x86,
+    CreatureHealth = 0x8C,
     CreatureLight = 0x8D,
+    CreatureSpeed = 0x8F,
     Vip = 0xD2,
     Icons = 0xA2,
+    CancelTarget = 0xA3,
     AddCreatureUnknown = 0x61,
     AddCreatureKnown = 0x62,
+    AddedEntity = 0x6A,
     ChangedEntity = 0x6B,
+    RemovedEntity = 0x6C,
     MovedEntity = 0x6D,
+    OpenedContainer = 0x6E,
+    CreatureSpeak = 0xAA,
+    ChannelList = 0xAB,
+    OpenedChannel = 0xAC,
+    OpenedPrivateChannel = 0xAD,
+    TextMessage = 0xB4,
+    CancelWalk = 0xB5,
 }
 
 #[derive(Debug, Clone)]
@@ -73,3 +112,9 @@ pub struct SkillEntry {
     pub level: u8,
     pub progress: u8,
 }
+
+#[derive(Debug, Clone)]
+pub struct ChannelEntry {
+    pub id: u16,
+    pub name: String,
+}
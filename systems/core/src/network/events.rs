@@ -2,19 +2,101 @@ use crate::prelude::*;
 use skyless_core::prelude::*;
 
 #[event]
-pub struct PingPayloadEvent;
+pub struct PingPayloadEvent {
+    pub player: CreatureId,
+}
 
 #[event]
-pub struct PingBackPayloadEvent;
+pub struct PingBackPayloadEvent {
+    pub player: CreatureId,
+}
 
 #[event]
 pub struct MovePayloadEvent {
-    pub player: Player,
+    pub player: CreatureId,
     pub direction: Direction,
 }
 
 #[event]
 pub struct UseItemPayloadEvent {
+    pub player: CreatureId,
     pub position: Position,
     pub item: Item,
 }
+
+#[event]
+pub struct UseItemOnPayloadEvent {
+    pub player: CreatureId,
+    pub from: Position,
+    pub from_item: Item,
+    pub to: Position,
+    pub to_item: Item,
+}
+
+#[event]
+pub struct TalkPayloadEvent {
+    pub player: CreatureId,
+    pub class: SpeakClasses,
+    pub receiver: Option<String>,
+    pub channel: Option<u16>,
+    pub text: String,
+}
+
+#[event]
+pub struct RequestChannelsPayloadEvent {
+    pub player: CreatureId,
+}
+
+#[event]
+pub struct OpenChannelPayloadEvent {
+    pub player: CreatureId,
+    pub channel: u16,
+}
+
+#[event]
+pub struct CloseChannelPayloadEvent {
+    pub player: CreatureId,
+    pub channel: u16,
+}
+
+#[event]
+pub struct OpenPrivateChannelPayloadEvent {
+    pub player: CreatureId,
+    pub name: String,
+}
+
+#[event]
+pub struct AttackPayloadEvent {
+    pub player: CreatureId,
+    pub target: u32,
+}
+
+#[event]
+pub struct FollowPayloadEvent {
+    pub player: CreatureId,
+    pub target: u32,
+}
+
+#[event]
+pub struct CancelPayloadEvent {
+    pub player: CreatureId,
+}
+
+#[event]
+pub struct PurchasePayloadEvent {
+    pub player: CreatureId,
+    pub item: u16,
+    pub amount: u8,
+}
+
+#[event]
+pub struct SellPayloadEvent {
+    pub player: CreatureId,
+    pub item: u16,
+    pub amount: u8,
+}
+
+#[event]
+pub struct CloseShopPayloadEvent {
+    pub player: CreatureId,
+}
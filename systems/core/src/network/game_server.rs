@@ -1,12 +1,16 @@
 use super::attributes::Clients;
 use super::{
-    definitions::{Client, Packet, SkillEntry},
+    definitions::{AccountName, Client, Packet},
     events::*,
-    payload::Payload,
+    payload::{client_position, spectators, Payload},
     protocol::{Reader, Writer},
     xtea::Xtea,
 };
-use crate::prelude::*;
+use crate::{
+    combat,
+    persistence::{self, DatabaseTrait},
+    prelude::*,
+};
 use futures::{
     sink::SinkExt,
     stream::{unfold, StreamExt},
@@ -22,6 +26,27 @@ use tokio::sync::{
     mpsc::{Receiver, Sender},
 };
 
+fn inventory_payloads(Inventory(inventory): &Inventory) -> Vec<Payload> {
+    [
+        InventorySlot::Head,
+        InventorySlot::Necklace,
+        InventorySlot::Backpack,
+        InventorySlot::Armor,
+        InventorySlot::Right,
+        InventorySlot::Left,
+        InventorySlot::Legs,
+        InventorySlot::Feet,
+        InventorySlot::Ring,
+        InventorySlot::Ammo,
+    ]
+    .into_iter()
+    .map(|slot| Payload::InventoryItem {
+        slot,
+        item: inventory.get(&slot).cloned(),
+    })
+    .collect()
+}
+
 system! {
     #[event]
     pub struct GameConnectionEvent {
@@ -30,10 +55,22 @@ system! {
     }
 
     #[event]
-    pub struct GameLoginEvent(pub Player, pub Client);
+    pub struct GameLoginEvent {
+        pub client: Client,
+        pub account: u32,
+        pub character: String,
+        pub password: String,
+    }
+
+    /// The character of the login as it was last saved, none when it could not be loaded.
+    #[event]
+    pub struct LoadedCharacterEvent {
+        pub client: Client,
+        pub loaded: Option<(Entity, Position)>,
+    }
 
     #[event]
-    pub struct GamePayloadEvent(pub Payload);
+    pub struct GamePayloadEvent(pub Payload, pub Client);
 
     #[effect(SystemsLoadedEvent)]
     fn init_players_attribute(_: EventType, _: GameAttributesType, _: WorldType) -> EffectResultType {
@@ -42,6 +79,9 @@ system! {
         Some((commands, Vec::new()))
     }
 
+    const ALREADY_ONLINE: &str = "You are already logged in.";
+    const WRONG_CHARACTER: &str = "Character could not be loaded.";
+
     const CHANNEL_BUFFER_SIZE: usize = 100;
     const IP: Ipv4Addr = Ipv4Addr::UNSPECIFIED;
     const GAME_PORT: u16 = 7172;
@@ -67,22 +107,24 @@ system! {
                 None
             }.into_stream()) as TaskType).await;
 
-            tasker.send(Box::pin(unfold((Some(sender_xtea), reader_tcp, writer), |(sender_xtea, mut reader_tcp, writer)| async move {
-                if let Some(Packet(payload)) = reader_tcp.next().await {
-                    let event = match payload {
-                        Payload::GameLogin { xtea, .. } => {
-                            reader_tcp.set_xtea(xtea);
-                            if let Some(sender_xtea) = sender_xtea {
-                                sender_xtea.send(xtea).unwrap();
-                            }
-                            Arc::new(GameLoginEvent(Player(1), Client(writer.clone()))) as EventType
-                        },
-                        _ => Arc::new(GamePayloadEvent(payload)) as EventType
-                    };
-                    Some((Some(event), (None, reader_tcp, writer)))
-                } else {
-                    Some((None, (None, reader_tcp, writer)))
-                }
+            tasker.send(Box::pin(unfold((Some(sender_xtea), Some(reader_tcp), writer), |(sender_xtea, reader_tcp, writer)| async move {
+                let mut reader_tcp = reader_tcp?;
+                let event = match reader_tcp.next().await {
+                    Some(Packet(Payload::GameLogin { xtea, name: AccountName::Old(account), character, password, .. })) => {
+                        reader_tcp.set_xtea(xtea);
+                        if let Some(sender_xtea) = sender_xtea {
+                            sender_xtea.send(xtea).unwrap();
+                        }
+                        Arc::new(GameLoginEvent { client: Client(writer.clone()), account, character, password }) as EventType
+                    },
+                    // the player leaves when asking for it or when the connection is lost, nothing is read afterwards
+                    Some(Packet(Payload::Logout)) | None => {
+                        let event = Arc::new(GamePayloadEvent(Payload::Logout, Client(writer.clone()))) as EventType;
+                        return Some((Some(event), (None, None, writer)));
+                    },
+                    Some(Packet(payload)) => Arc::new(GamePayloadEvent(payload, Client(writer.clone()))) as EventType,
+                };
+                Some((Some(event), (None, Some(reader_tcp), writer)))
             })) as TaskType).await;
         }
         yield None;
@@ -92,31 +134,75 @@ system! {
     fn handle_game_login(event: EventType, attributes: GameAttributesType, world: WorldType) -> EffectResultType {
         println!("Handle game login");
 
-        let GameLoginEvent(player, Client(client)) = event.as_any().downcast_ref::<GameLoginEvent>().unwrap();
+        let GameLoginEvent { client: Client(client), account, character, password } = event.as_any().downcast_ref::<GameLoginEvent>().unwrap();
+        let client = client.clone();
+        let loaded = if Player::find_by_name(&world.lock().unwrap(), character).is_some() {
+            Err(ALREADY_ONLINE)
+        } else {
+            attributes.lock().unwrap().database().map(|database| persistence::load_character(database, *account, password.clone(), character.clone())).ok_or(WRONG_CHARACTER)
+        };
+
+        // the character is looked up outside of the game loop and comes back with another event
+        let task = Box::pin(async move {
+            match loaded {
+                Ok(loaded) => Some(Arc::new(LoadedCharacterEvent { client: Client(client), loaded: loaded.await }) as EventType),
+                Err(text) => {
+                    client.send(Packet(Payload::Disconnect(text.into()))).await;
+                    None
+                },
+            }
+        }.into_stream()) as TaskType;
+
+        Some((Vec::new(), vec![task]))
+    }
+
+    #[effect(LoadedCharacterEvent)]
+    fn handle_loaded_character(event: EventType, attributes: GameAttributesType, world: WorldType) -> EffectResultType {
+        let LoadedCharacterEvent { client: Client(client), loaded } = event.as_any().downcast_ref::<LoadedCharacterEvent>().unwrap();
         let client_clone = client.clone();
         let mut commands = Vec::new();
         let mut tasks = Vec::new();
+
+        // the same character may have been loaded for another login meanwhile
+        let loaded = match loaded {
+            Some((player, _)) if player.name().is_some_and(|Name(name)| Player::find_by_name(&world.lock().unwrap(), name).is_some()) => Err(ALREADY_ONLINE),
+            Some(loaded) => Ok(loaded.clone()),
+            None => Err(WRONG_CHARACTER),
+        };
+        let (mut player, position) = match loaded {
+            Ok(loaded) => loaded,
+            Err(text) => {
+                tasks.push(Box::pin(async move {
+                    client_clone.send(Packet(Payload::Disconnect(text.into()))).await;
+                    None
+                }.into_stream()) as TaskType);
+                return Some((commands, tasks));
+            },
+        };
+        // the map may have changed since the character was saved
+        let position = match world.lock().unwrap().tile(&position) {
+            Some(_) => position,
+            None => combat::TEMPLE,
+        };
+
         let attributes = attributes.lock().unwrap();
+        let mut creature_ids = attributes.creature_ids().cloned().unwrap_or_default();
+        let creature = creature_ids.allocate();
+        commands.push(Box::new(SetGameAttributeCommand(Box::new(creature_ids))) as CommandType);
         if let Some(clients) = attributes.clients() {
             let mut clients_new = clients.0.clone();
-            clients_new.insert(player.clone(), Client(client_clone.clone()));
+            clients_new.insert(creature.clone(), Client(client_clone.clone()));
             commands.push(Box::new(SetGameAttributeCommand(Box::new(Clients(clients_new)))) as CommandType);
         }
+        player.attributes.insert(creature.as_name().to_string(), Box::new(creature.clone()));
 
-        let player = entity![
-            player.clone(),
-            Name("Skyless".into()),
-            Health { value: 150, max: 150 },
-            Direction(Directions::South),
-            Outfit { r#type: 128, head: 78, body: 69, legs: 58, feet: 76, addons: 0 },
-            LightInfo { level: 0xFF, color: 0x00 },
-            Speed(220),
-            Skull(Skulls::None),
-            PartyShield(PartyShields::None)
-        ];
-        let player_id = player.player().unwrap().0;
+        let CreatureId(player_id) = creature.clone();
+        let inventory = player.inventory().unwrap().clone();
+        let stats_payload = Payload::stats(&player).unwrap();
+        let skills_payload = Payload::skills(&player).unwrap();
+        let icons_payload = Payload::icons(&player);
 
-        commands.push(Box::new(AddEntityCommand { position: Position(128, 128, 7), entity: player }) as CommandType);
+        commands.push(Box::new(AddEntityCommand { position: position.clone(), entity: player }) as CommandType);
 
         let player_login_payload = Payload::PlayerLogin {
             id: player_id,
@@ -124,50 +210,15 @@ system! {
             can_report_bugs: false,
         };
         let map_payload = Payload::MapFull {
-            position: Position(128, 128, 7),
+            position,
             world,
         };
-        let mut inventory_items_payloads = vec![
-            Payload::InventoryItem { slot: InventorySlot::Head, item: None },
-            Payload::InventoryItem { slot: InventorySlot::Necklace, item: None },
-            Payload::InventoryItem { slot: InventorySlot::Backpack, item: None },
-            Payload::InventoryItem { slot: InventorySlot::Armor, item: None },
-            Payload::InventoryItem { slot: InventorySlot::Right, item: None },
-            Payload::InventoryItem { slot: InventorySlot::Left, item: None },
-            Payload::InventoryItem { slot: InventorySlot::Legs, item: None },
-            Payload::InventoryItem { slot: InventorySlot::Feet, item: None },
-            Payload::InventoryItem { slot: InventorySlot::Ring, item: None },
-            Payload::InventoryItem { slot: InventorySlot::Ammo, item: None },
-        ];
-        let stats_payload = Payload::Stats {
-            health: 150,
-            health_max: 150,
-            capacity: 400,
-            experience: 0,
-            level: 1,
-            level_progress: 0,
-            mana: 0,
-            mana_max: 0,
-            magic: 0,
-            magic_progress: 0,
-            soul: 100,
-            stamina: 56 * 60,
-        };
-        let skills_payload = Payload::Skills(vec![
-            SkillEntry { r#type: SkillType::Fist, level: 10, progress: 0 },
-            SkillEntry { r#type: SkillType::Club, level: 10, progress: 0 },
-            SkillEntry { r#type: SkillType::Sword, level: 10, progress: 0 },
-            SkillEntry { r#type: SkillType::Axe, level: 10, progress: 0 },
-            SkillEntry { r#type: SkillType::Distance, level: 10, progress: 0 },
-            SkillEntry { r#type: SkillType::Shield, level: 10, progress: 0 },
-            SkillEntry { r#type: SkillType::Fishing, level: 10, progress: 0 },
-        ]);
+        let mut inventory_items_payloads = inventory_payloads(&inventory);
         let world_light_payload = Payload::WorldLight(LightInfo { level: 0xFF, color: 0x00 });
         let creature_light_payload = Payload::CreatureLight {
-            player: Player(player_id),
-            light: LightInfo { level: 0xFF, color: 0x00 },
+            creature,
+            light: LightInfo::NONE,
         };
-        let icons_payload = Payload::Icons(0x00);
 
         let mut payloads = vec![player_login_payload, map_payload];
         payloads.append(&mut inventory_items_payloads);
@@ -177,6 +228,10 @@ system! {
             world_light_payload,
             creature_light_payload,
             icons_payload,
+            Payload::TextMessage {
+                class: MessageClasses::StatusDefault,
+                text: "Welcome to Skyless!".into(),
+            },
         ]);
         let packet = Packet(Payload::Combined(payloads));
 
@@ -189,23 +244,101 @@ system! {
     }
 
     #[effect(GamePayloadEvent)]
-    fn handle_game_payload(event: EventType, _: GameAttributesType, _: WorldType) -> EffectResultType {
+    fn handle_game_payload(event: EventType, attributes: GameAttributesType, _: WorldType) -> EffectResultType {
+        let GamePayloadEvent(_, Client(sender)) = event.as_any().downcast_ref::<GamePayloadEvent>().unwrap();
+        // payloads are told apart by the connection they came through
+        let player = attributes.lock().unwrap().clients().and_then(|Clients(clients)| {
+            clients
+                .iter()
+                .find(|(_, Client(client))| client.same_channel(sender))
+                .map(|(creature, _)| creature.clone())
+        });
+        let Some(player) = player else {
+            return Some((Vec::new(), Vec::new()));
+        };
         let tasks = vec![
             Box::pin(async move {
-                let GamePayloadEvent(payload) = event.as_any().downcast_ref::<GamePayloadEvent>().unwrap();
+                let GamePayloadEvent(payload, _) = event.as_any().downcast_ref::<GamePayloadEvent>().unwrap();
                 match payload {
-                    Payload::Ping => Some(Arc::new(PingPayloadEvent) as EventType),
-                    Payload::PingBack => Some(Arc::new(PingBackPayloadEvent) as EventType),
-                    Payload::Move { player, direction } =>
+                    Payload::Logout => Some(Arc::new(LogoutEvent { player }) as EventType),
+                    Payload::Ping => Some(Arc::new(PingPayloadEvent { player }) as EventType),
+                    Payload::PingBack => Some(Arc::new(PingBackPayloadEvent { player }) as EventType),
+                    Payload::Move { direction } =>
                         Some(Arc::new(MovePayloadEvent {
-                            player: player.clone(),
+                            player,
                             direction: direction.clone(),
                         }) as EventType),
                     Payload::UseItem { position, item } =>
                         Some(Arc::new(UseItemPayloadEvent {
+                            player,
                             position: position.clone(),
                             item: item.clone(),
                         }) as EventType),
+                    Payload::UseItemOn { from, from_item, to, to_item } =>
+                        Some(Arc::new(UseItemOnPayloadEvent {
+                            player,
+                            from: from.clone(),
+                            from_item: from_item.clone(),
+                            to: to.clone(),
+                            to_item: to_item.clone(),
+                        }) as EventType),
+                    Payload::Talk { class, receiver, channel, text } =>
+                        Some(Arc::new(TalkPayloadEvent {
+                            player,
+                            class: *class,
+                            receiver: receiver.clone(),
+                            channel: *channel,
+                            text: text.clone(),
+                        }) as EventType),
+                    Payload::RequestChannels =>
+                        Some(Arc::new(RequestChannelsPayloadEvent {
+                            player,
+                        }) as EventType),
+                    Payload::OpenChannel { channel } =>
+                        Some(Arc::new(OpenChannelPayloadEvent {
+                            player,
+                            channel: *channel,
+                        }) as EventType),
+                    Payload::CloseChannel { channel } =>
+                        Some(Arc::new(CloseChannelPayloadEvent {
+                            player,
+                            channel: *channel,
+                        }) as EventType),
+                    Payload::OpenPrivateChannel { name } =>
+                        Some(Arc::new(OpenPrivateChannelPayloadEvent {
+                            player,
+                            name: name.clone(),
+                        }) as EventType),
+                    Payload::Attack { target } =>
+                        Some(Arc::new(AttackPayloadEvent {
+                            player,
+                            target: *target,
+                        }) as EventType),
+                    Payload::Follow { target } =>
+                        Some(Arc::new(FollowPayloadEvent {
+                            player,
+                            target: *target,
+                        }) as EventType),
+                    Payload::Cancel =>
+                        Some(Arc::new(CancelPayloadEvent {
+                            player,
+                        }) as EventType),
+                    Payload::Purchase { item, amount } =>
+                        Some(Arc::new(PurchasePayloadEvent {
+                            player,
+                            item: *item,
+                            amount: *amount,
+                        }) as EventType),
+                    Payload::Sell { item, amount } =>
+                        Some(Arc::new(SellPayloadEvent {
+                            player,
+                            item: *item,
+                            amount: *amount,
+                        }) as EventType),
+                    Payload::CloseShop =>
+                        Some(Arc::new(CloseShopPayloadEvent {
+                            player,
+                        }) as EventType),
                     _ => None,
                 }
             }.into_stream()) as TaskType,
@@ -213,16 +346,39 @@ system! {
         Some((Vec::new(), tasks))
     }
 
+    // the client is forgotten last, once it is taken out of the world and nobody is after it anymore
+    #[effect(LogoutEvent)]
+    fn handle_logout(event: EventType, attributes: GameAttributesType, world: WorldType) -> EffectResultType {
+        let LogoutEvent { player } = event.as_any().downcast_ref::<LogoutEvent>().unwrap();
+        let game_attributes = attributes.lock().unwrap();
+        let world = world.lock().unwrap();
+        let mut commands = combat::untarget(&world, player);
+        if let Some(id) = world.find_creature(player) {
+            // the save is queued before a new login of the character can load it again
+            if let (Some(database), Some(entity), Some(position)) = (game_attributes.database(), world.entity(id), world.position(id)) {
+                persistence::save_character(database, entity.clone(), position);
+            }
+            commands.push(Box::new(RemoveEntityCommand { entity: id }) as CommandType);
+        }
+        if let Some(Clients(clients)) = game_attributes.clients() {
+            let mut clients = clients.clone();
+            clients.remove(player);
+            commands.push(Box::new(SetGameAttributeCommand(Box::new(Clients(clients)))) as CommandType);
+        }
+        Some((commands, Vec::new()))
+    }
+
     #[effect(ChangedEntityEvent)]
     fn handle_changed_entity(event: EventType, attributes: GameAttributesType, world: WorldType) -> EffectResultType {
-        let ChangedEntityEvent { position, attribute_name } = event.as_any().downcast_ref::<ChangedEntityEvent>().unwrap();
+        let ChangedEntityEvent { entity: id, attribute_name } = event.as_any().downcast_ref::<ChangedEntityEvent>().unwrap();
         let mut tasks = Vec::new();
         if attribute_name.as_str() == "item" { // TODO: somehow check attribute type instead of name
             let game_attributes = attributes.lock().unwrap();
             if let Some(mut tasks2) = (|| {
                 let clients = game_attributes.clients()?;
                 let world = world.lock().unwrap();
-                let entity = world.entity(&position)?;
+                let entity = world.entity(*id)?;
+                let position = client_position(&world.position(*id)?)?;
                 let item = entity.item()?;
                 let mut tasks = Vec::new();
                 for Client(client) in clients.0.values() {
@@ -240,4 +396,193 @@ system! {
         }
         Some((Vec::new(), tasks))
     }
+
+    #[effect(ChangedEntityEvent)]
+    fn handle_changed_inventory(event: EventType, attributes: GameAttributesType, world: WorldType) -> EffectResultType {
+        let ChangedEntityEvent { entity: id, attribute_name } = event.as_any().downcast_ref::<ChangedEntityEvent>().unwrap();
+        let mut tasks = Vec::new();
+        if attribute_name.as_str() != "inventory" {
+            return Some((Vec::new(), tasks));
+        }
+        let game_attributes = attributes.lock().unwrap();
+        let world = world.lock().unwrap();
+        if let Some(task) = (|| {
+            let entity = world.entity(*id)?;
+            let Client(client) = game_attributes.clients()?.0.get(entity.creature_id()?)?.clone();
+            let packet = Packet(Payload::Combined(inventory_payloads(entity.inventory()?)));
+            Some(Box::pin(async move {
+                client.send(packet).await;
+                None
+            }.into_stream()) as TaskType)
+        })() {
+            tasks.push(task);
+        }
+        Some((Vec::new(), tasks))
+    }
+
+    #[effect(ChangedEntityEvent)]
+    fn handle_changed_stats(event: EventType, attributes: GameAttributesType, world: WorldType) -> EffectResultType {
+        let ChangedEntityEvent { entity: id, attribute_name } = event.as_any().downcast_ref::<ChangedEntityEvent>().unwrap();
+        let mut tasks = Vec::new();
+        let game_attributes = attributes.lock().unwrap();
+        let world = world.lock().unwrap();
+        if let Some(task) = (|| {
+            let entity = world.entity(*id)?;
+            let payload = match attribute_name.as_str() {
+                "health" | "mana" | "soul" | "capacity" | "stamina" | "inventory" | "experience" | "level" | "magic_level" => {
+                    Payload::stats(entity)?
+                },
+                "skills" => Payload::skills(entity)?,
+                "conditions" => Payload::icons(entity),
+                _ => return None,
+            };
+            let Client(client) = game_attributes.clients()?.0.get(entity.creature_id()?)?.clone();
+            Some(Box::pin(async move {
+                client.send(Packet(payload)).await;
+                None
+            }.into_stream()) as TaskType)
+        })() {
+            tasks.push(task);
+        }
+        Some((Vec::new(), tasks))
+    }
+
+    #[effect(ChangedEntityEvent)]
+    fn handle_changed_speed(event: EventType, attributes: GameAttributesType, world: WorldType) -> EffectResultType {
+        let ChangedEntityEvent { entity: id, attribute_name } = event.as_any().downcast_ref::<ChangedEntityEvent>().unwrap();
+        let mut tasks = Vec::new();
+        if attribute_name.as_str() != "speed" {
+            return Some((Vec::new(), tasks));
+        }
+        let game_attributes = attributes.lock().unwrap();
+        let world = world.lock().unwrap();
+        if let Some(mut tasks2) = (|| {
+            let clients = game_attributes.clients()?;
+            let entity = world.entity(*id)?;
+            let position = world.position(*id)?;
+            let Speed(speed) = entity.speed()?;
+            let payload = Payload::CreatureSpeed { creature: entity.creature_id()?.0, speed: *speed };
+            let mut tasks = Vec::new();
+            for (spectator, _) in spectators(&world, &position) {
+                let Some(Client(client)) = clients.0.get(spectator) else {
+                    continue;
+                };
+                let client = client.clone();
+                let payload = payload.clone();
+                tasks.push(Box::pin(async move {
+                    client.send(Packet(payload)).await;
+                    None
+                }.into_stream()) as TaskType);
+            }
+            Some(tasks)
+        })() {
+            tasks.append(&mut tasks2);
+        }
+        Some((Vec::new(), tasks))
+    }
+
+    #[effect(ChangedEntityEvent)]
+    fn handle_changed_light(event: EventType, attributes: GameAttributesType, world: WorldType) -> EffectResultType {
+        let ChangedEntityEvent { entity: id, attribute_name } = event.as_any().downcast_ref::<ChangedEntityEvent>().unwrap();
+        let mut tasks = Vec::new();
+        if attribute_name.as_str() != "light_info" {
+            return Some((Vec::new(), tasks));
+        }
+        let game_attributes = attributes.lock().unwrap();
+        let world = world.lock().unwrap();
+        if let Some(mut tasks2) = (|| {
+            let clients = game_attributes.clients()?;
+            let entity = world.entity(*id)?;
+            let position = world.position(*id)?;
+            let payload = Payload::CreatureLight { creature: entity.creature_id()?.clone(), light: entity.light_info()?.clone() };
+            let mut tasks = Vec::new();
+            for (spectator, _) in spectators(&world, &position) {
+                let Some(Client(client)) = clients.0.get(spectator) else {
+                    continue;
+                };
+                let client = client.clone();
+                let payload = payload.clone();
+                tasks.push(Box::pin(async move {
+                    client.send(Packet(payload)).await;
+                    None
+                }.into_stream()) as TaskType);
+            }
+            Some(tasks)
+        })() {
+            tasks.append(&mut tasks2);
+        }
+        Some((Vec::new(), tasks))
+    }
+
+    #[effect(AddedEntityEvent)]
+    fn handle_added_entity(event: EventType, attributes: GameAttributesType, world: WorldType) -> EffectResultType {
+        let AddedEntityEvent { entity: id } = event.as_any().downcast_ref::<AddedEntityEvent>().unwrap();
+        let mut tasks = Vec::new();
+        let game_attributes = attributes.lock().unwrap();
+        if let Some(mut tasks2) = (|| {
+            let clients = game_attributes.clients()?;
+            let world_lock = world.lock().unwrap();
+            let creature = world_lock.entity(*id)?.creature_id();
+            let position = world_lock.position(*id)?;
+            // clients are not told about things beyond the ones they see on the tile
+            let payload = Payload::added_entity(&world_lock, *id)?;
+            let mut tasks = Vec::new();
+            for (spectator, _) in spectators(&world_lock, &position) {
+                if Some(spectator) == creature {
+                    continue;
+                }
+                let Some(Client(client)) = clients.0.get(spectator) else {
+                    continue;
+                };
+                let client = client.clone();
+                let payload = payload.clone();
+                tasks.push(Box::pin(async move {
+                    client.send(Packet(payload)).await;
+                    None
+                }.into_stream()) as TaskType);
+            }
+            Some(tasks)
+        })() {
+            tasks.append(&mut tasks2);
+        }
+        Some((Vec::new(), tasks))
+    }
+
+    #[effect(DespawnedEntityEvent)]
+    fn handle_removed_entity(event: EventType, attributes: GameAttributesType, world: WorldType) -> EffectResultType {
+        let DespawnedEntityEvent { position, .. } = event.as_any().downcast_ref::<DespawnedEntityEvent>().unwrap();
+        let mut tasks = Vec::new();
+        let game_attributes = attributes.lock().unwrap();
+        let world = world.lock().unwrap();
+        if let (Some(clients), Some(position)) = (game_attributes.clients(), client_position(position)) {
+            for (spectator, _) in spectators(&world, &position) {
+                let Some(Client(client)) = clients.0.get(spectator) else {
+                    continue;
+                };
+                let client = client.clone();
+                let payload = Payload::RemovedEntity { position: position.clone() };
+                tasks.push(Box::pin(async move {
+                    client.send(Packet(payload)).await;
+                    None
+                }.into_stream()) as TaskType);
+            }
+        }
+        Some((Vec::new(), tasks))
+    }
+
+    #[handler(SendTextCommand)]
+    fn handle_send_text(command: CommandType, attributes: GameAttributesType, _: WorldType) -> EffectResultType {
+        let SendTextCommand { player, class, text } = command.as_any().downcast_ref::<SendTextCommand>().unwrap();
+        let mut tasks = Vec::new();
+        let game_attributes = attributes.lock().unwrap();
+        if let Some(Client(client)) = game_attributes.clients().and_then(|clients| clients.0.get(player)) {
+            let client = client.clone();
+            let payload = Payload::TextMessage { class: *class, text: text.clone() };
+            tasks.push(Box::pin(async move {
+                client.send(Packet(payload)).await;
+                None
+            }.into_stream()) as TaskType);
+        }
+        Some((Vec::new(), tasks))
+    }
 }
@@ -0,0 +1,154 @@
+use super::super::{
+    definitions::{ChannelEntry, Client, Packet},
+    events::*,
+    payload::Payload,
+};
+use crate::prelude::*;
+use futures::FutureExt;
+use skyless_core::prelude::*;
+
+pub const PLAYER_NOT_FOUND: &str = "A player with this name does not exist.";
+pub const PLAYER_NOT_ONLINE: &str = "A player with this name is not online.";
+
+/// Why the player named `name` could not be reached, the name may belong to a character that is offline.
+pub fn out_of_reach(game_attributes: &GameAttributes, name: &str) -> &'static str {
+    match game_attributes.character_names() {
+        Some(names) if names.contains(name) => PLAYER_NOT_ONLINE,
+        _ => PLAYER_NOT_FOUND,
+    }
+}
+
+system! {
+    #[effect(SystemsLoadedEvent)]
+    fn init_channels_attribute(_: EventType, _: GameAttributesType, _: WorldType) -> EffectResultType {
+        // there are no guilds nor parties yet, so their channels are left out
+        let channels = HashMap::from([
+            (DefaultChannels::GameChat.into(), Channel::new("Game-Chat", true)),
+            (DefaultChannels::Trade.into(), Channel::new("Trade", true)),
+            (DefaultChannels::RealLife.into(), Channel::new("RL-Chat", true)),
+            (DefaultChannels::Help.into(), Channel::new("Help", true)),
+        ]);
+        let commands = vec![Box::new(SetGameAttributeCommand(Box::new(Channels(channels)))) as CommandType];
+        Some((commands, Vec::new()))
+    }
+
+    #[effect(RequestChannelsPayloadEvent)]
+    fn handle_request_channels_payload(event: EventType, attributes: GameAttributesType, _: WorldType) -> EffectResultType {
+        let RequestChannelsPayloadEvent { player } = event.as_any().downcast_ref::<RequestChannelsPayloadEvent>().unwrap();
+        let mut tasks = Vec::new();
+
+        if let Some(task) = (|| {
+            let game_attributes = attributes.lock().unwrap();
+            let Client(client) = game_attributes.clients()?.0.get(player)?.clone();
+            let mut channels = game_attributes.channels()?.0.iter()
+                .filter(|(_, channel)| channel.is_visible_to(player))
+                .map(|(id, channel)| ChannelEntry { id: *id, name: channel.name.clone() })
+                .collect::<Vec<_>>();
+            channels.sort_by_key(|channel| channel.id);
+            Some(Box::pin(async move {
+                client.send(Packet(Payload::ChannelList(channels))).await;
+                None
+            }.into_stream()) as TaskType)
+        })() {
+            tasks.push(task);
+        }
+
+        Some((Vec::new(), tasks))
+    }
+
+    #[effect(OpenChannelPayloadEvent)]
+    fn handle_open_channel_payload(event: EventType, attributes: GameAttributesType, _: WorldType) -> EffectResultType {
+        let OpenChannelPayloadEvent { player, channel } = event.as_any().downcast_ref::<OpenChannelPayloadEvent>().unwrap();
+        let mut commands = Vec::new();
+        let mut tasks = Vec::new();
+
+        if let Some((command, task)) = (|| {
+            let game_attributes = attributes.lock().unwrap();
+            let Client(client) = game_attributes.clients()?.0.get(player)?.clone();
+            let mut channels = game_attributes.channels()?.0.clone();
+            let opened = channels.get_mut(channel).filter(|opened| opened.is_visible_to(player))?;
+            opened.members.insert(player.clone());
+            let entry = ChannelEntry { id: *channel, name: opened.name.clone() };
+            Some((
+                Box::new(SetGameAttributeCommand(Box::new(Channels(channels)))) as CommandType,
+                Box::pin(async move {
+                    client.send(Packet(Payload::OpenedChannel(entry))).await;
+                    None
+                }.into_stream()) as TaskType,
+            ))
+        })() {
+            commands.push(command);
+            tasks.push(task);
+        }
+
+        Some((commands, tasks))
+    }
+
+    #[effect(CloseChannelPayloadEvent)]
+    fn handle_close_channel_payload(event: EventType, attributes: GameAttributesType, _: WorldType) -> EffectResultType {
+        let CloseChannelPayloadEvent { player, channel } = event.as_any().downcast_ref::<CloseChannelPayloadEvent>().unwrap();
+        let mut commands = Vec::new();
+
+        if let Some(command) = (|| {
+            let game_attributes = attributes.lock().unwrap();
+            let mut channels = game_attributes.channels()?.0.clone();
+            channels.get_mut(channel)?.members.remove(player).then_some(())?;
+            Some(Box::new(SetGameAttributeCommand(Box::new(Channels(channels)))) as CommandType)
+        })() {
+            commands.push(command);
+        }
+
+        Some((commands, Vec::new()))
+    }
+
+    #[effect(OpenPrivateChannelPayloadEvent)]
+    fn handle_open_private_channel_payload(event: EventType, attributes: GameAttributesType, world: WorldType) -> EffectResultType {
+        let OpenPrivateChannelPayloadEvent { player, name } = event.as_any().downcast_ref::<OpenPrivateChannelPayloadEvent>().unwrap();
+        let mut commands = Vec::new();
+        let mut tasks = Vec::new();
+
+        let game_attributes = attributes.lock().unwrap();
+        let clients = game_attributes.clients()?;
+        let world = world.lock().unwrap();
+        match Player::find_by_name(&world, name) {
+            Some((receiver, id)) if clients.0.get(&receiver).is_some_and(|Client(client)| !client.is_closed()) => {
+                if let Some(task) = (|| {
+                    let Client(client) = clients.0.get(player)?.clone();
+                    let Name(name) = world.entity(id)?.name()?;
+                    let payload = Payload::OpenedPrivateChannel(name.clone());
+                    Some(Box::pin(async move {
+                        client.send(Packet(payload)).await;
+                        None
+                    }.into_stream()) as TaskType)
+                })() {
+                    tasks.push(task);
+                }
+            },
+            Some(_) => commands.push(Box::new(SendTextCommand::cancel(player.clone(), PLAYER_NOT_ONLINE)) as CommandType),
+            None => commands.push(Box::new(SendTextCommand::cancel(player.clone(), out_of_reach(&game_attributes, name))) as CommandType),
+        };
+
+        Some((commands, tasks))
+    }
+
+    #[effect(LogoutEvent)]
+    fn handle_logout_channels(event: EventType, attributes: GameAttributesType, _: WorldType) -> EffectResultType {
+        let LogoutEvent { player } = event.as_any().downcast_ref::<LogoutEvent>().unwrap();
+        let mut commands = Vec::new();
+
+        if let Some(command) = (|| {
+            let game_attributes = attributes.lock().unwrap();
+            let mut channels = game_attributes.channels()?.0.clone();
+            let mut left = false;
+            for channel in channels.values_mut() {
+                left |= channel.members.remove(player);
+            }
+            left.then_some(())?;
+            Some(Box::new(SetGameAttributeCommand(Box::new(Channels(channels)))) as CommandType)
+        })() {
+            commands.push(command);
+        }
+
+        Some((commands, Vec::new()))
+    }
+}
@@ -0,0 +1,146 @@
+use super::super::{
+    definitions::{Client, Packet},
+    events::{AttackPayloadEvent, CancelPayloadEvent, FollowPayloadEvent},
+    payload::{spectators, Payload},
+};
+use crate::prelude::*;
+use futures::FutureExt;
+use skyless_core::{prelude::*, World};
+use std::time::Instant;
+
+const SQUARE_BLACK: u8 = 0;
+const NOT_ATTACKABLE: &str = "You may not attack this person.";
+
+fn set_target(
+    world: &World,
+    player: &CreatureId,
+    target: u32,
+    mode: TargetModes,
+) -> Option<CommandType> {
+    let id = world.find_creature(player)?;
+    let entity = world.entity(id)?;
+    if target == 0 || entity.creature_id() == Some(&CreatureId(target)) {
+        return Some(Box::new(RemoveEntityAttributeCommand {
+            entity: id,
+            attribute: Box::new(entity.target()?.clone()),
+        }) as CommandType);
+    }
+    // NPCs are there to talk to, not to fight
+    let is_npc = world
+        .find_creature(&CreatureId(target))
+        .and_then(|target| world.entity(target)?.npc().cloned())
+        .is_some();
+    if is_npc && mode == TargetModes::Attack {
+        return Some(
+            Box::new(SendTextCommand::cancel(player.clone(), NOT_ATTACKABLE)) as CommandType,
+        );
+    }
+    Some(Box::new(SetEntityAttributeCommand {
+        entity: id,
+        attribute: Box::new(Target {
+            creature: CreatureId(target),
+            mode,
+            since: Instant::now(),
+        }),
+    }) as CommandType)
+}
+
+system! {
+    #[effect(AttackPayloadEvent)]
+    fn handle_attack_payload(event: EventType, _: GameAttributesType, world: WorldType) -> EffectResultType {
+        let AttackPayloadEvent { player, target } = event.as_any().downcast_ref::<AttackPayloadEvent>().unwrap();
+        let world = world.lock().unwrap();
+        let commands = set_target(&world, player, *target, TargetModes::Attack).into_iter().collect();
+        Some((commands, Vec::new()))
+    }
+
+    #[effect(FollowPayloadEvent)]
+    fn handle_follow_payload(event: EventType, _: GameAttributesType, world: WorldType) -> EffectResultType {
+        let FollowPayloadEvent { player, target } = event.as_any().downcast_ref::<FollowPayloadEvent>().unwrap();
+        let world = world.lock().unwrap();
+        let commands = set_target(&world, player, *target, TargetModes::Follow).into_iter().collect();
+        Some((commands, Vec::new()))
+    }
+
+    #[effect(CancelPayloadEvent)]
+    fn handle_cancel_payload(event: EventType, _: GameAttributesType, world: WorldType) -> EffectResultType {
+        let CancelPayloadEvent { player } = event.as_any().downcast_ref::<CancelPayloadEvent>().unwrap();
+        let world = world.lock().unwrap();
+        let commands = set_target(&world, player, 0, TargetModes::Attack).into_iter().collect();
+        Some((commands, Vec::new()))
+    }
+
+    #[effect(RemovedEntityEvent)]
+    fn handle_target_removed(event: EventType, attributes: GameAttributesType, world: WorldType) -> EffectResultType {
+        let RemovedEntityEvent { entity, attribute_name } = event.as_any().downcast_ref::<RemovedEntityEvent>().unwrap();
+        let mut tasks = Vec::new();
+        if attribute_name.as_str() != "target" {
+            return Some((Vec::new(), tasks));
+        }
+        let game_attributes = attributes.lock().unwrap();
+        let world = world.lock().unwrap();
+        if let Some(task) = (|| {
+            let player = world.entity(*entity)?.creature_id()?;
+            let Client(client) = game_attributes.clients()?.0.get(player)?.clone();
+            Some(Box::pin(async move {
+                client.send(Packet(Payload::CancelTarget)).await;
+                None
+            }.into_stream()) as TaskType)
+        })() {
+            tasks.push(task);
+        }
+        Some((Vec::new(), tasks))
+    }
+
+    #[effect(ChangedEntityEvent)]
+    fn handle_health_changed(event: EventType, attributes: GameAttributesType, world: WorldType) -> EffectResultType {
+        let ChangedEntityEvent { entity: id, attribute_name } = event.as_any().downcast_ref::<ChangedEntityEvent>().unwrap();
+        let mut tasks = Vec::new();
+        if attribute_name.as_str() != "health" {
+            return Some((Vec::new(), tasks));
+        }
+        let game_attributes = attributes.lock().unwrap();
+        let world = world.lock().unwrap();
+        if let Some(mut tasks2) = (|| {
+            let clients = game_attributes.clients()?;
+            let entity = world.entity(*id)?;
+            let position = world.position(*id)?;
+            let CreatureId(creature) = entity.creature_id()?;
+            let payload = Payload::CreatureHealth { creature: *creature, percent: entity.health()?.percent() };
+            let mut tasks = Vec::new();
+            for (spectator, _) in spectators(&world, &position) {
+                let Some(Client(client)) = clients.0.get(spectator) else {
+                    continue;
+                };
+                let client = client.clone();
+                let payload = payload.clone();
+                tasks.push(Box::pin(async move {
+                    client.send(Packet(payload)).await;
+                    None
+                }.into_stream()) as TaskType);
+            }
+            Some(tasks)
+        })() {
+            tasks.append(&mut tasks2);
+        }
+        Some((Vec::new(), tasks))
+    }
+
+    #[effect(HitEvent)]
+    fn handle_hit(event: EventType, attributes: GameAttributesType, _: WorldType) -> EffectResultType {
+        let HitEvent { attacker, target, .. } = event.as_any().downcast_ref::<HitEvent>().unwrap();
+        let mut tasks = Vec::new();
+        let game_attributes = attributes.lock().unwrap();
+        if let Some(task) = (|| {
+            let Client(client) = game_attributes.clients()?.0.get(target)?.clone();
+            let payload = Payload::Square { creature: attacker.0, color: SQUARE_BLACK };
+            Some(Box::pin(async move {
+                client.send(Packet(payload)).await;
+                None
+            }.into_stream()) as TaskType)
+        })() {
+            tasks.push(task);
+        }
+        Some((Vec::new(), tasks))
+    }
+}
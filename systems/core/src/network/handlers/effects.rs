@@ -0,0 +1,43 @@
+use super::super::{
+    definitions::{Client, Packet},
+    payload::{spectators, Payload},
+};
+use crate::prelude::*;
+use futures::FutureExt;
+use skyless_core::prelude::*;
+use std::collections::HashSet;
+
+system! {
+    #[handler(ShowEffectCommand)]
+    fn handle_show_effect(command: CommandType, attributes: GameAttributesType, world: WorldType) -> EffectResultType {
+        let ShowEffectCommand { position, effect } = command.as_any().downcast_ref::<ShowEffectCommand>().unwrap();
+        let mut tasks = Vec::new();
+        let game_attributes = attributes.lock().unwrap();
+        let clients = game_attributes.clients()?;
+        let world = world.lock().unwrap();
+
+        let payload = match effect.clone() {
+            VisualEffects::Magic(effect) => Payload::MagicEffect { position: position.clone(), effect },
+            VisualEffects::Distance { to, shoot } => Payload::DistanceShot { from: position.clone(), to, effect: shoot },
+            VisualEffects::AnimatedText { color, text } => Payload::AnimatedText { position: position.clone(), color, text },
+        };
+        // a missile is seen by anyone having either end of its flight in view
+        let mut viewers: HashSet<&CreatureId> = spectators(&world, position).map(|(viewer, _)| viewer).collect();
+        if let VisualEffects::Distance { to, .. } = effect {
+            viewers.extend(spectators(&world, to).map(|(viewer, _)| viewer));
+        }
+        for viewer in viewers {
+            let Some(Client(client)) = clients.0.get(viewer) else {
+                continue;
+            };
+            let client = client.clone();
+            let payload = payload.clone();
+            tasks.push(Box::pin(async move {
+                client.send(Packet(payload)).await;
+                None
+            }.into_stream()) as TaskType);
+        }
+
+        Some((Vec::new(), tasks))
+    }
+}
@@ -1,3 +1,8 @@
+pub mod channels;
+pub mod combat;
+pub mod effects;
 pub mod r#move;
 pub mod ping;
+pub mod shop;
+pub mod talk;
 pub mod use_item;
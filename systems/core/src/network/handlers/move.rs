@@ -1,5 +1,9 @@
-use super::super::{definitions::Packet, events::MovePayloadEvent, payload::Payload};
-use crate::prelude::*;
+use super::super::{
+    definitions::{Client, Packet},
+    events::MovePayloadEvent,
+    payload::{can_see, client_position, spectators, Payload},
+};
+use crate::{geometry, prelude::*};
 use futures::FutureExt;
 use skyless_core::prelude::*;
 use std::time::{Duration, Instant};
@@ -13,19 +17,15 @@ system! {
         let mut tasks = Vec::new();
 
         if let Some((command, task)) = (|| {
-            let position_and_player_entity = world.0.iter().enumerate()
-                .find_map(|(index, (pos, tile))| tile.entities.iter().find(|entity| match entity.player() {
-                    Some(Player(player_id)) => *player_id == player.0,
-                    _ => false,
-                })
-                .map(|entity| (pos.clone().stack_pos(Some(index as u16)), entity)))?;
-            let (position, player_entity) = position_and_player_entity;
+            let id = world.find_creature(player)?;
+            let position = world.position(id)?.stack_pos(None);
+            let player_entity = world.entity(id)?;
             let can_walk = player_entity.walking().map_or_else(|| true, |w| Instant::now().checked_duration_since(w.until).is_some());
             if can_walk {
                 let player_speed = player_entity.speed()?;
                 let ground_speed = 150;
                 let command = Box::new(SetEntityAttributeCommand {
-                    position: position.clone(),
+                    entity: id,
                     attribute: Box::new(Walking {
                         until: Instant::now() + Duration::from_millis((1000 * ground_speed as u64) / player_speed.0 as u64),
                     }),
@@ -33,7 +33,7 @@ system! {
                 let event = Arc::new(MoveEvent {
                     from: position.clone(),
                     to: direction.clone().apply_to_position(position.clone()),
-                    player: Some(player.clone()),
+                    creature: player.clone(),
                 }) as EventType;
                 Some((
                     command,
@@ -53,82 +53,98 @@ system! {
     }
 
     #[effect(MoveEvent)]
-    fn handle_move(event: EventType, _: GameAttributesType, world: WorldType) -> EffectResultType {
-        let MoveEvent { from, to, player } = event.as_any().downcast_ref::<MoveEvent>().unwrap();
+    fn handle_move(event: EventType, attributes: GameAttributesType, world: WorldType) -> EffectResultType {
+        let MoveEvent { from, to, creature } = event.as_any().downcast_ref::<MoveEvent>().unwrap();
         let world = world.lock().unwrap();
         let mut commands = Vec::new();
+        let mut tasks = Vec::new();
+
+        // open level and quest doors still keep out whoever could not have opened them
+        let mover = world.find_creature(creature).and_then(|id| world.entity(id));
+        let blocked = world.tile(to).is_none() || world.entities(to).any(|(_, entity)| {
+            entity.creature_id().is_some()
+                || entity.item().is_some_and(Item::is_blocking)
+                || entity.door().is_some_and(|door| mover.is_none_or(|mover| !door.can_pass(mover)))
+        });
+        if blocked {
+            if let Some(task) = (|| {
+                let game_attributes = attributes.lock().unwrap();
+                let Client(client) = game_attributes.clients()?.0.get(creature)?.clone();
+                let direction = Direction::between_positions(from.clone(), to.clone())?;
+                Some(Box::pin(async move {
+                    client.send(Packet(Payload::CancelWalk(direction))).await;
+                    None
+                }.into_stream()) as TaskType)
+            })() {
+                tasks.push(task);
+            }
+            return Some((commands, tasks));
+        }
 
         if let Some(command) = (|| {
-            let from_tile = world.tile(from)?;
-            let id = player.clone()?.0;
-            let player_entity_pos = from_tile.entities.iter().position(|entity| match entity.player() {
-                Some(Player(player_id)) => *player_id == id,
-                _ => false,
-            })?;
+            // the creature could have been moved away meanwhile
+            let id = world.find_creature(creature)?;
+            if world.position(id)?.stack_pos(None) != from.clone().stack_pos(None) {
+                return None;
+            }
             Some(Box::new(MoveEntityCommand {
-                from: from.clone().stack_pos(Some(player_entity_pos as u16)),
+                entity: id,
                 to: to.clone(),
             }) as CommandType)
         })() {
             commands.push(command);
         }
 
-        Some((commands, Vec::new()))
+        Some((commands, tasks))
     }
 
     #[effect(MovedEntityEvent)]
     fn handle_moved_entity(event: EventType, attributes: GameAttributesType, world: WorldType) -> EffectResultType {
-        let MovedEntityEvent { from, to } = event.as_any().downcast_ref::<MovedEntityEvent>().unwrap();
+        let MovedEntityEvent { entity: id, from, to } = event.as_any().downcast_ref::<MovedEntityEvent>().unwrap();
         let world_clone = world.clone();
         let world_lock = world_clone.lock().unwrap();
         let mut commands = Vec::new();
         let mut tasks = Vec::new();
 
-        let from_tile = world_lock.tile(&from)?;
-        let to_tile = world_lock.tile(&to)?;
-        let entity_stack_pos = (to_tile.entities.len() - 1) as u16;
-        let entity = to_tile.entities.get(entity_stack_pos as usize).unwrap();
+        let entity = world_lock.entity(*id)?;
 
-        if let Some(events) = (|| {
-            let entity_position = to.clone().stack_pos(Some(entity_stack_pos));
-            let mut events = Vec::new();
-            for (stack_pos, _) in from_tile.entities.iter().enumerate() {
-                events.push(Arc::new(SeparationEvent {
-                    first: from.clone().stack_pos(Some(stack_pos as u16)),
-                    second: entity_position.clone(),
-                }) as EventType);
-            }
-            for (stack_pos, _) in to_tile.entities[0..to_tile.entities.len() - 1].iter().enumerate() {
-                events.push(Arc::new(CollisionEvent {
-                    first: to.clone().stack_pos(Some(stack_pos as u16)),
-                    second: entity_position.clone(),
-                }) as EventType);
-            }
-            Some(events)
-        })() {
-            let mut events = events.into_iter().map(|event| Box::new(EmitEventCommand(event)) as CommandType).collect();
-            commands.append(&mut events);
+        for (other, _) in world_lock.entities(from) {
+            commands.push(Box::new(EmitEventCommand(Arc::new(SeparationEvent { first: other, second: *id }))) as CommandType);
+        }
+        for (other, _) in world_lock.entities(to).filter(|(other, _)| other != id) {
+            commands.push(Box::new(EmitEventCommand(Arc::new(CollisionEvent { first: other, second: *id }))) as CommandType);
         }
 
+        // clients only know of the creature where it was, or get to see it where it is, within the visible things
+        let client_from = client_position(from);
+        let visible_to = world_lock.position(*id).as_ref().and_then(client_position).is_some();
+
         if let Some(task) = (|| {
             let world = world.clone();
-            let player = entity.player()?;
+            let player = entity.creature_id()?;
             let game_attributes = attributes.lock().unwrap();
             let clients = game_attributes.clients()?;
             let client = clients.0.get(player)?.clone();
-            let direction = Direction::between_positions(from.clone(), to.clone())?;
-            let map_payload = match direction.0 {
-                Directions::North => Some(Payload::MapNorth { position: from.clone(), world }),
-                Directions::West => Some(Payload::MapWest { position: to.clone(), world }),
-                Directions::South => Some(Payload::MapSouth { position: to.clone(), world }),
-                Directions::East => Some(Payload::MapEast { position: to.clone(), world }),
+            let direction = Direction::between_positions(from.clone(), to.clone()).map(|Direction(direction)| direction);
+            let map_payload = match direction {
+                Some(Directions::North) => Some(Payload::MapNorth { position: from.clone(), world: world.clone() }),
+                Some(Directions::West) => Some(Payload::MapWest { position: to.clone(), world: world.clone() }),
+                Some(Directions::South) => Some(Payload::MapSouth { position: to.clone(), world: world.clone() }),
+                Some(Directions::East) => Some(Payload::MapEast { position: to.clone(), world: world.clone() }),
                 _ => None,
-            }?;
-            let from_stack_pos = from.stack_pos.map(|pos| if pos == 0 { pos } else { from_tile.entities.len() as u16 - pos + 1 });
-            let packet = Packet(Payload::Combined(vec![
-                Payload::MovedEntity { from: from.clone().stack_pos(from_stack_pos), to: to.clone() },
-                map_payload,
-            ]));
+            };
+            // anything but a single step (teleports, floor changes) needs the whole map to be resent
+            let packet = Packet(Payload::Combined(match (map_payload, client_from.clone()) {
+                (Some(map_payload), Some(from)) => vec![
+                    Payload::MovedEntity { from, to: to.clone() },
+                    map_payload,
+                ],
+                (_, from) => from
+                    .map(|position| Payload::RemovedEntity { position })
+                    .into_iter()
+                    .chain([Payload::MapFull { position: to.clone(), world }])
+                    .collect(),
+            }));
             Some(Box::pin(async move {
                 client.0.send(packet).await;
                 None
@@ -137,6 +153,56 @@ system! {
             tasks.push(task);
         }
 
+        // everybody else sees the creature step, or enter and leave their view
+        if let Some(mut spectator_tasks) = (|| {
+            entity.creature_id()?;
+            let game_attributes = attributes.lock().unwrap();
+            let mut tasks = Vec::new();
+            let clients = game_attributes.clients()?;
+            let added = Payload::added_entity(&world_lock, *id);
+            let viewers: HashMap<&CreatureId, Position> = spectators(&world_lock, from).chain(spectators(&world_lock, to)).collect();
+            for (viewer_id, viewer) in viewers {
+                if entity.creature_id() == Some(viewer_id) {
+                    continue;
+                }
+                let Some(Client(client)) = clients.0.get(viewer_id) else {
+                    continue;
+                };
+                let seen_to = visible_to && can_see(&viewer, to);
+                let payload = match client_from.clone().filter(|from| can_see(&viewer, from)) {
+                    Some(from) if seen_to => Payload::MovedEntity { from, to: to.clone() },
+                    Some(from) => Payload::RemovedEntity { position: from },
+                    None => match added.clone().filter(|_| seen_to) {
+                        Some(added) => added,
+                        None => continue,
+                    },
+                };
+                let client = client.clone();
+                tasks.push(Box::pin(async move {
+                    client.send(Packet(payload)).await;
+                    None
+                }.into_stream()) as TaskType);
+            }
+            Some(tasks)
+        })() {
+            tasks.append(&mut spectator_tasks);
+        }
+
         Some((commands, tasks))
     }
+
+    // nobody is put into a wall or onto somebody else, the closest free tile is taken instead
+    #[effect(TeleportEvent)]
+    fn handle_teleport(event: EventType, _: GameAttributesType, world: WorldType) -> EffectResultType {
+        let TeleportEvent { entity, to } = event.as_any().downcast_ref::<TeleportEvent>().unwrap();
+        let world = world.lock().unwrap();
+        let commands = match geometry::walkable_near(&world, to) {
+            Some(to) => vec![
+                Box::new(MoveEntityCommand { entity: *entity, to: to.clone() }) as CommandType,
+                Box::new(ShowEffectCommand { position: to, effect: VisualEffects::Magic(MagicEffects::Teleport) }) as CommandType,
+            ],
+            None => Vec::new(),
+        };
+        Some((commands, Vec::new()))
+    }
 }
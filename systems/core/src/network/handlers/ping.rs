@@ -1,5 +1,6 @@
 use super::super::{
     definitions::{Client, Packet},
+    events::{PingBackPayloadEvent, PingPayloadEvent},
     payload::Payload,
 };
 use crate::prelude::*;
@@ -7,12 +8,13 @@ use futures::FutureExt;
 use skyless_core::prelude::*;
 
 system! {
-     #[effect(PingPayloadEvent)]
-    fn handle_ping_payload(_: EventType, attributes: GameAttributesType, _: WorldType) -> EffectResultType {
+    #[effect(PingPayloadEvent)]
+    fn handle_ping_payload(event: EventType, attributes: GameAttributesType, _: WorldType) -> EffectResultType {
+        let PingPayloadEvent { player } = event.as_any().downcast_ref::<PingPayloadEvent>().unwrap();
         let mut tasks = Vec::new();
         let game_attributes = attributes.lock().unwrap();
         if let Some(clients) = game_attributes.clients() {
-            if let Some(Client(client)) = clients.0.get(&Player(1)) { // TODO: get rid of hardcoded player id
+            if let Some(Client(client)) = clients.0.get(player) {
                 let client = client.clone();
                 tasks.push(Box::pin(async move {
                     client.send(Packet(Payload::Ping)).await;
@@ -24,11 +26,12 @@ system! {
     }
 
     #[effect(PingBackPayloadEvent)]
-    fn handle_ping_back_payload(_: EventType, attributes: GameAttributesType, _: WorldType) -> EffectResultType {
+    fn handle_ping_back_payload(event: EventType, attributes: GameAttributesType, _: WorldType) -> EffectResultType {
+        let PingBackPayloadEvent { player } = event.as_any().downcast_ref::<PingBackPayloadEvent>().unwrap();
         let mut tasks = Vec::new();
         let game_attributes = attributes.lock().unwrap();
         if let Some(clients) = game_attributes.clients() {
-            if let Some(Client(client)) = clients.0.get(&Player(1)) { // TODO: get rid of hardcoded player id
+            if let Some(Client(client)) = clients.0.get(player) {
                 let client = client.clone();
                 tasks.push(Box::pin(async move {
                     client.send(Packet(Payload::Ping)).await;
@@ -0,0 +1,102 @@
+use super::super::{
+    definitions::{Client, Packet},
+    events::{CloseShopPayloadEvent, PurchasePayloadEvent, SellPayloadEvent},
+    payload::Payload,
+};
+use crate::prelude::*;
+use futures::FutureExt;
+use skyless_core::prelude::*;
+
+system! {
+    #[effect(PurchasePayloadEvent)]
+    fn handle_purchase_payload(event: EventType, _: GameAttributesType, _: WorldType) -> EffectResultType {
+        let PurchasePayloadEvent { player, item, amount } = event.as_any().downcast_ref::<PurchasePayloadEvent>().unwrap();
+        let commands = vec![Box::new(EmitEventCommand(Arc::new(BuyEvent {
+            player: player.clone(),
+            item: *item,
+            amount: *amount,
+        }))) as CommandType];
+        Some((commands, Vec::new()))
+    }
+
+    #[effect(SellPayloadEvent)]
+    fn handle_sell_payload(event: EventType, _: GameAttributesType, _: WorldType) -> EffectResultType {
+        let SellPayloadEvent { player, item, amount } = event.as_any().downcast_ref::<SellPayloadEvent>().unwrap();
+        let commands = vec![Box::new(EmitEventCommand(Arc::new(SellEvent {
+            player: player.clone(),
+            item: *item,
+            amount: *amount,
+        }))) as CommandType];
+        Some((commands, Vec::new()))
+    }
+
+    #[effect(CloseShopPayloadEvent)]
+    fn handle_close_shop_payload(event: EventType, _: GameAttributesType, world: WorldType) -> EffectResultType {
+        let CloseShopPayloadEvent { player } = event.as_any().downcast_ref::<CloseShopPayloadEvent>().unwrap();
+        let world = world.lock().unwrap();
+        let commands = (|| {
+            let id = world.find_creature(player)?;
+            let shop = world.entity(id)?.shop()?.clone();
+            Some(Box::new(RemoveEntityAttributeCommand {
+                entity: id,
+                attribute: Box::new(shop),
+            }) as CommandType)
+        })()
+        .into_iter()
+        .collect();
+        Some((commands, Vec::new()))
+    }
+
+    // the goods are sent again whenever the inventory changes while trading
+    #[effect(ChangedEntityEvent)]
+    fn handle_changed_shop(event: EventType, attributes: GameAttributesType, world: WorldType) -> EffectResultType {
+        let ChangedEntityEvent { entity: id, attribute_name } = event.as_any().downcast_ref::<ChangedEntityEvent>().unwrap();
+        let mut tasks = Vec::new();
+        if !matches!(attribute_name.as_str(), "shop" | "inventory") {
+            return Some((Vec::new(), tasks));
+        }
+        let game_attributes = attributes.lock().unwrap();
+        let world = world.lock().unwrap();
+        if let Some(task) = (|| {
+            let entity = world.entity(*id)?;
+            let Shop(npc) = entity.shop()?;
+            let Client(client) = game_attributes.clients()?.0.get(entity.creature_id()?)?.clone();
+            let mut payloads = Vec::new();
+            if attribute_name.as_str() == "shop" {
+                let Npc(name) = world.entity(world.find_creature(npc)?)?.npc()?;
+                let npc_type = game_attributes.npc_types()?.0.get(name)?;
+                payloads.push(Payload::OpenedShop(npc_type.shop.clone()));
+            }
+            payloads.push(Payload::player_goods(entity)?);
+            Some(Box::pin(async move {
+                client.send(Packet(Payload::Combined(payloads))).await;
+                None
+            }.into_stream()) as TaskType)
+        })() {
+            tasks.push(task);
+        }
+        Some((Vec::new(), tasks))
+    }
+
+    #[effect(RemovedEntityEvent)]
+    fn handle_shop_closed(event: EventType, attributes: GameAttributesType, world: WorldType) -> EffectResultType {
+        let RemovedEntityEvent { entity, attribute_name } = event.as_any().downcast_ref::<RemovedEntityEvent>().unwrap();
+        let mut tasks = Vec::new();
+        if attribute_name.as_str() != "shop" {
+            return Some((Vec::new(), tasks));
+        }
+        let game_attributes = attributes.lock().unwrap();
+        let world = world.lock().unwrap();
+        if let Some(task) = (|| {
+            let player = world.entity(*entity)?.creature_id()?;
+            let Client(client) = game_attributes.clients()?.0.get(player)?.clone();
+            Some(Box::pin(async move {
+                client.send(Packet(Payload::ClosedShop)).await;
+                None
+            }.into_stream()) as TaskType)
+        })() {
+            tasks.push(task);
+        }
+        Some((Vec::new(), tasks))
+    }
+}
@@ -0,0 +1,163 @@
+use super::super::{
+    definitions::{Client, Packet},
+    events::TalkPayloadEvent,
+    payload::Payload,
+};
+use super::channels::{out_of_reach, PLAYER_NOT_ONLINE};
+use crate::{geometry::DEEPEST_FLOOR, prelude::*};
+use futures::FutureExt;
+use skyless_core::prelude::*;
+
+const WHISPER_OVERHEARD: &str = "pspsps";
+
+system! {
+    #[effect(TalkPayloadEvent)]
+    fn handle_talk_payload(event: EventType, _: GameAttributesType, world: WorldType) -> EffectResultType {
+        let TalkPayloadEvent { player, class, text, .. } = event.as_any().downcast_ref::<TalkPayloadEvent>().unwrap();
+        let world = world.lock().unwrap();
+        let mut commands = Vec::new();
+
+        if let Some(event) = (|| {
+            class.range()?;
+            let position = world.position(world.find_creature(player)?)?;
+            Some(Arc::new(SpeakEvent {
+                player: Some(player.clone()),
+                position: position.stack_pos(None),
+                class: *class,
+                text: text.clone(),
+            }) as EventType)
+        })() {
+            commands.push(Box::new(EmitEventCommand(event)) as CommandType);
+        }
+
+        Some((commands, Vec::new()))
+    }
+
+    #[effect(TalkPayloadEvent)]
+    fn handle_channel_talk_payload(event: EventType, attributes: GameAttributesType, world: WorldType) -> EffectResultType {
+        let TalkPayloadEvent { player, class, channel, text, .. } = event.as_any().downcast_ref::<TalkPayloadEvent>().unwrap();
+        let mut tasks = Vec::new();
+
+        if let Some(mut channel_tasks) = (|| {
+            let channel = (*channel)?;
+            let game_attributes = attributes.lock().unwrap();
+            let clients = game_attributes.clients()?;
+            let members = &game_attributes.channels()?.0.get(&channel)?.members;
+            if !members.contains(player) {
+                return None;
+            }
+            let world = world.lock().unwrap();
+            let Name(name) = world.entity(world.find_creature(player)?)?.name()?;
+            let mut tasks = Vec::new();
+            for member in members.iter() {
+                let Some(Client(client)) = clients.0.get(member).cloned() else {
+                    continue;
+                };
+                let payload = Payload::CreatureSpeak {
+                    name: name.clone(),
+                    class: *class,
+                    position: None,
+                    channel: Some(channel),
+                    text: text.clone(),
+                };
+                tasks.push(Box::pin(async move {
+                    client.send(Packet(payload)).await;
+                    None
+                }.into_stream()) as TaskType);
+            }
+            Some(tasks)
+        })() {
+            tasks.append(&mut channel_tasks);
+        }
+
+        Some((Vec::new(), tasks))
+    }
+
+    #[effect(TalkPayloadEvent)]
+    fn handle_private_talk_payload(event: EventType, attributes: GameAttributesType, world: WorldType) -> EffectResultType {
+        let TalkPayloadEvent { player, class, receiver, text, .. } = event.as_any().downcast_ref::<TalkPayloadEvent>().unwrap();
+        let mut commands = Vec::new();
+        let mut tasks = Vec::new();
+
+        let receiver = receiver.as_ref()?;
+        let game_attributes = attributes.lock().unwrap();
+        let clients = game_attributes.clients()?;
+        let world = world.lock().unwrap();
+        match Player::find_by_name(&world, receiver) {
+            Some((receiver, _)) if clients.0.get(&receiver).is_some_and(|Client(client)| !client.is_closed()) => {
+                if let Some(task) = (|| {
+                    let Client(client) = clients.0.get(&receiver)?.clone();
+                    let Name(name) = world.entity(world.find_creature(player)?)?.name()?;
+                    let payload = Payload::CreatureSpeak {
+                        name: name.clone(),
+                        class: *class,
+                        position: None,
+                        channel: None,
+                        text: text.clone(),
+                    };
+                    Some(Box::pin(async move {
+                        client.send(Packet(payload)).await;
+                        None
+                    }.into_stream()) as TaskType)
+                })() {
+                    tasks.push(task);
+                }
+            },
+            Some(_) => commands.push(Box::new(SendTextCommand::cancel(player.clone(), PLAYER_NOT_ONLINE)) as CommandType),
+            None => commands.push(Box::new(SendTextCommand::cancel(player.clone(), out_of_reach(&game_attributes, receiver))) as CommandType),
+        };
+
+        Some((commands, tasks))
+    }
+
+    #[effect(SpeakEvent)]
+    fn handle_speak(event: EventType, attributes: GameAttributesType, world: WorldType) -> EffectResultType {
+        let SpeakEvent { player, position, class, text } = event.as_any().downcast_ref::<SpeakEvent>().unwrap();
+        let world = world.lock().unwrap();
+        let mut tasks = Vec::new();
+
+        if let Some(mut speak_tasks) = (|| {
+            let game_attributes = attributes.lock().unwrap();
+            let clients = game_attributes.clients()?;
+            let speaker = player.as_ref().and_then(|player| world.find_creature(player)).or_else(|| world.entity_at(position))?;
+            let Name(name) = world.entity(speaker)?.name()?;
+            // everyone around notices a whisper, but only the closest ones can understand it
+            let hearing_class = match class {
+                SpeakClasses::Whisper => SpeakClasses::Say,
+                _ => *class,
+            };
+            let (range_x, range_y, multi_floor) = hearing_class.range()?;
+            let (min_z, max_z) = if multi_floor { (0, DEEPEST_FLOOR) } else { (position.z, position.z) };
+            let from = Position(position.x.saturating_sub(range_x), position.y.saturating_sub(range_y), min_z);
+            let to = Position(position.x.saturating_add(range_x), position.y.saturating_add(range_y), max_z);
+            let mut tasks = Vec::new();
+            for (listener, listener_id) in world.creatures_in(from, to) {
+                let (Some(Client(client)), Some(listener_position)) = (clients.0.get(listener), world.position(listener_id)) else {
+                    continue;
+                };
+                let text = if class.can_hear(position, &listener_position) {
+                    text.clone()
+                } else {
+                    WHISPER_OVERHEARD.to_string()
+                };
+                let client = client.clone();
+                let payload = Payload::CreatureSpeak {
+                    name: name.clone(),
+                    class: *class,
+                    position: Some(position.clone()),
+                    channel: None,
+                    text,
+                };
+                tasks.push(Box::pin(async move {
+                    client.send(Packet(payload)).await;
+                    None
+                }.into_stream()) as TaskType);
+            }
+            Some(tasks)
+        })() {
+            tasks.append(&mut speak_tasks);
+        }
+
+        Some((Vec::new(), tasks))
+    }
+}
@@ -1,21 +1,27 @@
-use super::super::events::UseItemPayloadEvent;
-use crate::prelude::*;
+use super::super::{
+    definitions::{Client, Packet},
+    events::{UseItemOnPayloadEvent, UseItemPayloadEvent},
+    payload::Payload,
+};
+use crate::{geometry, prelude::*};
+use futures::FutureExt;
 use skyless_core::prelude::*;
 
 system! {
     #[effect(UseItemPayloadEvent)]
     fn handle_use_item_payload(event: EventType, _: GameAttributesType, world: WorldType) -> EffectResultType {
-        let UseItemPayloadEvent { position, item } = event.as_any().downcast_ref::<UseItemPayloadEvent>().unwrap();
+        let UseItemPayloadEvent { player, position, item } = event.as_any().downcast_ref::<UseItemPayloadEvent>().unwrap();
         let world = world.lock().unwrap();
         let mut commands = Vec::new();
 
         if let Some(event) = (|| {
-            let entity = world.entity(position)?;
-            let entity_item = entity.item()?;
+            let target = world.entity_at(position)?;
+            let entity_item = world.entity(target)?.item()?;
             if entity_item.0 == item.0 {
                 Some(Arc::new(UseEvent {
+                    player: Some(player.clone()),
                     source: None,
-                    target: position.clone(),
+                    target,
                 }))
             } else {
                 None
@@ -26,4 +32,64 @@ system! {
 
         Some((commands, Vec::new()))
     }
+
+    #[effect(UseItemOnPayloadEvent)]
+    fn handle_use_item_on_payload(event: EventType, _: GameAttributesType, world: WorldType) -> EffectResultType {
+        let UseItemOnPayloadEvent { player, from, from_item, to, to_item } = event.as_any().downcast_ref::<UseItemOnPayloadEvent>().unwrap();
+        let world = world.lock().unwrap();
+        let mut commands = Vec::new();
+
+        if let Some(event) = (|| {
+            let source = match Inventory::slot(from) {
+                Some(_) => {
+                    let player = world.find_creature(player)?;
+                    world.entity(player)?.inventory()?.at(from)?
+                },
+                None => world.entity(world.entity_at(from)?)?,
+            };
+            let target = world.entity_at(to)?;
+            if source.item()?.0 == from_item.0 && world.entity(target)?.item()?.0 == to_item.0 {
+                Some(Arc::new(UseEvent {
+                    player: Some(player.clone()),
+                    source: Some(from.clone()),
+                    target,
+                }))
+            } else {
+                None
+            }
+        })() {
+            commands.push(Box::new(EmitEventCommand(event)) as CommandType);
+        }
+
+        Some((commands, Vec::new()))
+    }
+
+    #[effect(UseEvent)]
+    fn handle_open_container(event: EventType, attributes: GameAttributesType, world: WorldType) -> EffectResultType {
+        let UseEvent { player, source, target } = event.as_any().downcast_ref::<UseEvent>().unwrap();
+        let mut tasks = Vec::new();
+        if source.is_some() {
+            return Some((Vec::new(), tasks));
+        }
+        let game_attributes = attributes.lock().unwrap();
+        let world = world.lock().unwrap();
+        if let Some(task) = (|| {
+            let player = player.as_ref()?;
+            let container = world.entity(*target)?;
+            container.container()?;
+            let player_position = world.position(world.find_creature(player)?)?;
+            if !geometry::is_adjacent(&player_position, &world.position(*target)?) {
+                return None;
+            }
+            let Client(client) = game_attributes.clients()?.0.get(player)?.clone();
+            let payload = Payload::OpenedContainer { id: 0, container: container.clone() };
+            Some(Box::pin(async move {
+                client.send(Packet(payload)).await;
+                None
+            }.into_stream()) as TaskType)
+        })() {
+            tasks.push(task);
+        }
+        Some((Vec::new(), tasks))
+    }
 }
@@ -1,13 +1,10 @@
 use super::{
-    definitions::{CharacterListEntry, Client, Packet},
+    definitions::{AccountName, CharacterListEntry, Client, Packet},
     payload::Payload,
     protocol::{Reader, Writer},
 };
-use crate::attributes::Player;
-use futures::{
-    sink::SinkExt,
-    stream::{unfold, StreamExt},
-};
+use crate::persistence::{self, DatabaseTrait};
+use futures::{sink::SinkExt, stream::StreamExt, FutureExt};
 use skyless_core::prelude::*;
 use std::net::{Ipv4Addr, SocketAddrV4};
 use std::str::FromStr;
@@ -16,7 +13,13 @@ use tokio::sync::mpsc;
 
 system! {
     #[event]
-    pub struct LoginEvent(Player, Client);
+    pub struct LoginEvent {
+        client: Client,
+        account: u32,
+        password: String,
+    }
+
+    const WRONG_ACCOUNT: &str = "Account number or password is not correct.";
 
     const CHANNEL_BUFFER_SIZE: usize = 100;
     const IP: Ipv4Addr = Ipv4Addr::UNSPECIFIED;
@@ -32,9 +35,9 @@ system! {
             let (sender, mut receiver) = mpsc::channel::<Packet>(CHANNEL_BUFFER_SIZE);
             let mut reader = Reader::new(reader);
             let mut writer = Writer::new(writer);
-            if let Some(Packet(Payload::Login { xtea, .. })) = reader.next().await {
+            if let Some(Packet(Payload::Login { xtea, name: AccountName::Old(account), password, .. })) = reader.next().await {
                 writer.set_xtea(xtea);
-                yield Some(Arc::new(LoginEvent(Player(1), Client(sender))) as EventType);
+                yield Some(Arc::new(LoginEvent { client: Client(sender), account, password }) as EventType);
                 if let Some(packet) = receiver.recv().await {
                     writer.send(packet).await;
                     writer.close().await;
@@ -44,28 +47,34 @@ system! {
     }
 
     #[effect(LoginEvent)]
-    fn handle_login(event: EventType, _: GameAttributesType, _: WorldType) -> EffectResultType {
+    fn handle_login(event: EventType, attributes: GameAttributesType, _: WorldType) -> EffectResultType {
         println!("Handle login");
 
-        let task = Box::pin(unfold(event, |event| async move {
-            let LoginEvent(_, Client(client)) = event.as_any().downcast_ref::<LoginEvent>().unwrap();
-            let character_list_packet = Packet(
-                Payload::CharacterList {
+        let LoginEvent { client: Client(client), account, password } = event.as_any().downcast_ref::<LoginEvent>().unwrap();
+        let client = client.clone();
+        let characters = attributes.lock().unwrap().database().map(|database| persistence::characters(database, *account, password.clone()));
+
+        let task = Box::pin(async move {
+            let characters = match characters {
+                Some(characters) => characters.await,
+                None => None,
+            };
+            let payload = match characters {
+                Some(characters) => Payload::CharacterList {
                     motd: "Welcome to Skyless POC!".into(),
-                    characters: vec![
-                        CharacterListEntry {
-                            name: "Test".into(),
-                            world: "Skyless".into(),
-                            ip: Ipv4Addr::from_str("10.211.55.2").unwrap().into(),
-                            port: GAME_PORT,
-                        },
-                    ],
+                    characters: characters.into_iter().map(|name| CharacterListEntry {
+                        name,
+                        world: "Skyless".into(),
+                        ip: Ipv4Addr::from_str("10.211.55.2").unwrap().into(),
+                        port: GAME_PORT,
+                    }).collect(),
                     premium_days: 0,
                 },
-            );
-            client.send(character_list_packet).await;
-            Some((None, event))
-        })) as TaskType;
+                None => Payload::LoginError(WRONG_ACCOUNT.into()),
+            };
+            client.send(Packet(payload)).await;
+            None
+        }.into_stream()) as TaskType;
 
         Some((Vec::new(), vec![task]))
     }
@@ -1,12 +1,76 @@
 use super::definitions::*;
 use super::xtea::Xtea;
-use crate::prelude::*;
+use crate::{geometry::DEEPEST_FLOOR, prelude::*, shops};
 use bytes::{BufMut, BytesMut};
 use itertools::Itertools;
-use skyless_core::prelude::*;
+use skyless_core::{prelude::*, World};
 
 const VIEWPORT_X: u16 = 8;
 const VIEWPORT_Y: u16 = 6;
+/// Most things clients know of on a tile, the ones beyond are left out of what they are sent.
+pub const MAX_VISIBLE_THINGS: u16 = 10;
+
+/// Whether a client centered at `viewer` has `position` within its map view.
+pub fn can_see(viewer: &Position, position: &Position) -> bool {
+    if viewer.z <= 7 {
+        if position.z > 7 {
+            return false;
+        }
+    } else if viewer.z.abs_diff(position.z) > 2 {
+        return false;
+    }
+    let offset = i32::from(viewer.z) - i32::from(position.z);
+    let (x, y) = (i32::from(position.x), i32::from(position.y));
+    let (viewer_x, viewer_y) = (i32::from(viewer.x), i32::from(viewer.y));
+    let (range_x, range_y) = (i32::from(VIEWPORT_X), i32::from(VIEWPORT_Y));
+    x >= viewer_x - range_x + offset
+        && x <= viewer_x + range_x + 1 + offset
+        && y >= viewer_y - range_y + offset
+        && y <= viewer_y + range_y + 1 + offset
+}
+
+/// The position clients address the thing at `position` by, tiles are kept in the order clients
+/// stack them so only the things beyond the visible ones have none.
+pub fn client_position(position: &Position) -> Option<Position> {
+    let stack_pos = position
+        .stack_pos
+        .filter(|stack_pos| *stack_pos < MAX_VISIBLE_THINGS)?;
+    Some(position.clone().stack_pos(Some(stack_pos)))
+}
+
+/// Stack position of the thing clients address by `stack_pos`, none for things they cannot know of.
+pub fn server_stack_pos(stack_pos: u8) -> Option<u16> {
+    Some(u16::from(stack_pos)).filter(|stack_pos| *stack_pos < MAX_VISIBLE_THINGS)
+}
+
+/// Creatures with `position` in their view, found without going through the whole map.
+pub fn spectators<'a>(
+    world: &'a World,
+    position: &'a Position,
+) -> impl Iterator<Item = (&'a CreatureId, Position)> + 'a {
+    let (min_z, max_z) = if position.z <= 7 {
+        (0, (position.z + 2).max(7))
+    } else {
+        ((position.z - 2).max(8), (position.z + 2).min(DEEPEST_FLOOR))
+    };
+    // the view of other floors is shifted by a tile per floor
+    let floors = u16::from((position.z - min_z).max(max_z - position.z));
+    let (range_x, range_y) = (VIEWPORT_X + 1 + floors, VIEWPORT_Y + 1 + floors);
+    let from = Position(
+        position.x.saturating_sub(range_x),
+        position.y.saturating_sub(range_y),
+        min_z,
+    );
+    let to = Position(
+        position.x.saturating_add(range_x),
+        position.y.saturating_add(range_y),
+        max_z,
+    );
+    world
+        .creatures_in(from, to)
+        .filter_map(|(creature, id)| Some((creature, world.position(id)?)))
+        .filter(move |(_, viewer)| can_see(viewer, position))
+}
 
 #[derive(Clone)]
 pub enum Payload {
@@ -23,6 +87,7 @@ pub enum Payload {
         characters: Vec<CharacterListEntry>,
         premium_days: u16,
     },
+    LoginError(String),
     GameLogin {
         version: u16,
         xtea: Xtea,
@@ -30,6 +95,8 @@ pub enum Payload {
         character: String,
         password: String,
     },
+    Disconnect(String),
+    Logout,
     Ping,
     PingBack,
     PlayerLogin {
@@ -65,12 +132,12 @@ pub enum Payload {
     },
     InventoryItem {
         slot: InventorySlot,
-        item: Option<Item>,
+        item: Option<Entity>,
     },
     Stats {
         health: u16,
         health_max: u16,
-        capacity: u16,
+        capacity: u32,
         experience: u32,
         level: u16,
         level_progress: u8,
@@ -84,18 +151,24 @@ pub enum Payload {
     Skills(Vec<SkillEntry>),
     WorldLight(LightInfo),
     CreatureLight {
-        player: Player,
+        creature: CreatureId,
         light: LightInfo,
     },
-    Icons(u16), // TODO: use Vec of conditions instead?
+    Icons(Vec<ConditionType>),
     Move {
-        player: Player,
         direction: Direction,
     },
     MovedEntity {
         from: Position,
         to: Position,
     },
+    AddedEntity {
+        position: Position,
+        entity: Entity,
+    },
+    RemovedEntity {
+        position: Position,
+    },
     ChangedEntity {
         position: Position,
         item: Item,
@@ -104,6 +177,96 @@ pub enum Payload {
         position: Position,
         item: Item,
     },
+    UseItemOn {
+        from: Position,
+        from_item: Item,
+        to: Position,
+        to_item: Item,
+    },
+    CancelWalk(Direction),
+    Attack {
+        target: u32,
+    },
+    Follow {
+        target: u32,
+    },
+    Cancel,
+    Purchase {
+        item: u16,
+        amount: u8,
+    },
+    Sell {
+        item: u16,
+        amount: u8,
+    },
+    CloseShop,
+    OpenedShop(Vec<ShopOffer>),
+    PlayerGoods {
+        money: u64,
+        items: Vec<(u16, u32)>,
+    },
+    ClosedShop,
+    OpenedContainer {
+        id: u8,
+        container: Entity,
+    },
+    Square {
+        creature: u32,
+        color: u8,
+    },
+    CancelTarget,
+    CreatureHealth {
+        creature: u32,
+        percent: u8,
+    },
+    CreatureSpeed {
+        creature: u32,
+        speed: u16,
+    },
+    Talk {
+        class: SpeakClasses,
+        receiver: Option<String>,
+        channel: Option<u16>,
+        text: String,
+    },
+    CreatureSpeak {
+        name: String,
+        class: SpeakClasses,
+        position: Option<Position>,
+        channel: Option<u16>,
+        text: String,
+    },
+    RequestChannels,
+    OpenChannel {
+        channel: u16,
+    },
+    CloseChannel {
+        channel: u16,
+    },
+    OpenPrivateChannel {
+        name: String,
+    },
+    ChannelList(Vec<ChannelEntry>),
+    OpenedChannel(ChannelEntry),
+    OpenedPrivateChannel(String),
+    TextMessage {
+        class: MessageClasses,
+        text: String,
+    },
+    MagicEffect {
+        position: Position,
+        effect: MagicEffects,
+    },
+    DistanceShot {
+        from: Position,
+        to: Position,
+        effect: ShootTypes,
+    },
+    AnimatedText {
+        position: Position,
+        color: TextColors,
+        text: String,
+    },
 }
 
 impl Payload {
@@ -122,7 +285,7 @@ impl Payload {
                 premium_days,
             } => {
                 let mut msg = BytesMut::new();
-                msg.put_u8(ServerOpcodes::CharacterList.into());
+                msg.put_u8(LoginServerOpcodes::CharacterList.into());
                 msg.put_u16_le(motd.len() as u16);
                 msg.put_slice(motd.as_bytes());
                 msg.put_u8(0x64);
@@ -138,6 +301,20 @@ impl Payload {
                 msg.put_u16_le(premium_days);
                 msg.to_vec()
             }
+            Self::LoginError(text) => {
+                let mut msg = BytesMut::new();
+                msg.put_u8(LoginServerOpcodes::Error.into());
+                msg.put_u16_le(text.len() as u16);
+                msg.put_slice(text.as_bytes());
+                msg.to_vec()
+            }
+            Self::Disconnect(text) => {
+                let mut msg = BytesMut::new();
+                msg.put_u8(ServerOpcodes::Disconnect.into());
+                msg.put_u16_le(text.len() as u16);
+                msg.put_slice(text.as_bytes());
+                msg.to_vec()
+            }
             Self::PlayerLogin {
                 id,
                 beat,
@@ -145,7 +322,7 @@ impl Payload {
             } => {
                 let mut msg = BytesMut::new();
                 msg.put_u8(ServerOpcodes::GameLogin.into());
-                msg.put_u32(id);
+                msg.put_u32_le(id);
                 msg.put_u16_le(beat);
                 msg.put_u8(can_report_bugs as u8);
                 msg.to_vec()
@@ -170,58 +347,23 @@ impl Payload {
                 let world = world.lock().unwrap();
                 let mut skip: i16 = -1;
                 for z in z_range {
-                    let offset = u16::from(position_z - z);
+                    let offset = i32::from(position_z) - i32::from(z);
                     for (x, y) in x_y_range.clone() {
-                        match world.0.get(&Position(x + offset, y + offset, z)) {
-                            Some(tile) => {
+                        let x = (i32::from(x) + offset) as u16;
+                        let y = (i32::from(y) + offset) as u16;
+                        let position = Position(x, y, z);
+                        match world.tile(&position) {
+                            Some(_) => {
                                 if skip >= 0 {
                                     msg.put_u8(skip as u8);
                                     msg.put_u8(0xFF);
                                 }
                                 skip = 0;
-                                for entity in tile.entities.iter() {
-                                    if let Some(Player(id)) = entity.player() {
-                                        // TODO: handle known creature
-                                        msg.put_u16_le(0x61);
-                                        msg.put_u32_le(0x00);
-                                        msg.put_u32(*id);
-                                        let Name(name) = entity.name().unwrap();
-                                        msg.put_u16_le(name.len() as u16);
-                                        msg.put_slice(name.as_bytes());
-                                        // TODO: handle creature health hidden case
-                                        let Health { value, max } = entity.health().unwrap();
-                                        let health_percentage = *value as f64 / *max as f64;
-                                        msg.put_u8(health_percentage.ceil() as u8 * 100);
-                                        let Direction(direction) = entity.direction().unwrap();
-                                        msg.put_u8(*direction as u8);
-                                        let Outfit {
-                                            r#type,
-                                            head,
-                                            body,
-                                            legs,
-                                            feet,
-                                            addons,
-                                        } = entity.outfit().unwrap();
-                                        msg.put_u16_le(*r#type);
-                                        msg.put_u8(*head);
-                                        msg.put_u8(*body);
-                                        msg.put_u8(*legs);
-                                        msg.put_u8(*feet);
-                                        msg.put_u8(*addons);
-                                        let LightInfo { level, color } =
-                                            entity.light_info().unwrap();
-                                        msg.put_u8(*level); // TODO: send 0xFF when "access player" (gm, god etc.)
-                                        msg.put_u8(*color);
-                                        let Speed(speed) = entity.speed().unwrap();
-                                        msg.put_u16_le(*speed);
-                                        let Skull(skull) = entity.skull().unwrap();
-                                        msg.put_u8(*skull as u8);
-                                        let PartyShield(party_shield) =
-                                            entity.party_shield().unwrap();
-                                        msg.put_u8(*party_shield as u8);
-                                    } else if let Some(Item(id)) = entity.item() {
-                                        msg.put_u16_le(*id);
-                                    }
+                                for (_, entity) in world
+                                    .entities(&position)
+                                    .take(usize::from(MAX_VISIBLE_THINGS))
+                                {
+                                    msg.put_slice(&entity_to_bytes(entity));
                                 }
                             }
                             None => {
@@ -325,10 +467,10 @@ impl Payload {
             Self::InventoryItem { slot, item } => {
                 let mut msg = BytesMut::new();
                 match item {
-                    Some(Item(id)) => {
+                    Some(entity) => {
                         msg.put_u8(ServerOpcodes::InventoryItem.into());
                         msg.put_u8(slot.into());
-                        msg.put_u16_le(id);
+                        msg.put_slice(&entity_to_bytes(&entity));
                     }
                     None => {
                         msg.put_u8(ServerOpcodes::InventoryEmpty.into());
@@ -355,7 +497,7 @@ impl Payload {
                 msg.put_u8(ServerOpcodes::Stats.into());
                 msg.put_u16_le(health);
                 msg.put_u16_le(health_max);
-                msg.put_u16_le(capacity / 100);
+                msg.put_u16_le((capacity / 100) as u16);
                 msg.put_u32_le(experience);
                 msg.put_u16_le(level);
                 msg.put_u8(level_progress);
@@ -387,7 +529,7 @@ impl Payload {
                 msg.to_vec()
             }
             Self::CreatureLight {
-                player: Player(id),
+                creature: CreatureId(id),
                 light: LightInfo { level, color },
             } => {
                 let mut msg = BytesMut::new();
@@ -400,7 +542,29 @@ impl Payload {
             Self::Icons(icons) => {
                 let mut msg = BytesMut::new();
                 msg.put_u8(ServerOpcodes::Icons.into());
-                msg.put_u16(icons);
+                msg.put_u8(
+                    icons
+                        .iter()
+                        .fold(0, |icons, condition| icons | condition.icon()),
+                );
+                msg.to_vec()
+            }
+            Self::AddedEntity { position, entity } => {
+                let mut msg = BytesMut::new();
+                msg.put_u8(ServerOpcodes::AddedEntity.into());
+                msg.put_u16_le(position.x);
+                msg.put_u16_le(position.y);
+                msg.put_u8(position.z);
+                msg.put_slice(&entity_to_bytes(&entity));
+                msg.to_vec()
+            }
+            Self::RemovedEntity { position } => {
+                let mut msg = BytesMut::new();
+                msg.put_u8(ServerOpcodes::RemovedEntity.into());
+                msg.put_u16_le(position.x);
+                msg.put_u16_le(position.y);
+                msg.put_u8(position.z);
+                msg.put_u8(position.stack_pos.unwrap() as u8);
                 msg.to_vec()
             }
             Self::ChangedEntity { position, item } => {
@@ -425,6 +589,187 @@ impl Payload {
                 msg.put_u8(to.z);
                 msg.to_vec()
             }
+            Self::CancelWalk(Direction(direction)) => {
+                let mut msg = BytesMut::new();
+                msg.put_u8(ServerOpcodes::CancelWalk.into());
+                msg.put_u8(direction.into());
+                msg.to_vec()
+            }
+            Self::Square { creature, color } => {
+                let mut msg = BytesMut::new();
+                msg.put_u8(ServerOpcodes::Square.into());
+                msg.put_u32_le(creature);
+                msg.put_u8(color);
+                msg.to_vec()
+            }
+            Self::CancelTarget => {
+                let mut msg = BytesMut::new();
+                msg.put_u8(ServerOpcodes::CancelTarget.into());
+                msg.to_vec()
+            }
+            Self::CreatureSpeed { creature, speed } => {
+                let mut msg = BytesMut::new();
+                msg.put_u8(ServerOpcodes::CreatureSpeed.into());
+                msg.put_u32_le(creature);
+                msg.put_u16_le(speed);
+                msg.to_vec()
+            }
+            Self::CreatureHealth { creature, percent } => {
+                let mut msg = BytesMut::new();
+                msg.put_u8(ServerOpcodes::CreatureHealth.into());
+                msg.put_u32_le(creature);
+                msg.put_u8(percent);
+                msg.to_vec()
+            }
+            Self::CreatureSpeak {
+                name,
+                class,
+                position,
+                channel,
+                text,
+            } => {
+                let mut msg = BytesMut::new();
+                msg.put_u8(ServerOpcodes::CreatureSpeak.into());
+                msg.put_u16_le(name.len() as u16);
+                msg.put_slice(name.as_bytes());
+                msg.put_u8(class.into());
+                if let Some(position) = position {
+                    msg.put_u16_le(position.x);
+                    msg.put_u16_le(position.y);
+                    msg.put_u8(position.z);
+                } else if let Some(channel) = channel {
+                    msg.put_u16_le(channel);
+                }
+                msg.put_u16_le(text.len() as u16);
+                msg.put_slice(text.as_bytes());
+                msg.to_vec()
+            }
+            Self::ChannelList(channels) => {
+                let mut msg = BytesMut::new();
+                msg.put_u8(ServerOpcodes::ChannelList.into());
+                msg.put_u8(channels.len() as u8);
+                for ChannelEntry { id, name } in channels.iter() {
+                    msg.put_u16_le(*id);
+                    msg.put_u16_le(name.len() as u16);
+                    msg.put_slice(name.as_bytes());
+                }
+                msg.to_vec()
+            }
+            Self::OpenedChannel(ChannelEntry { id, name }) => {
+                let mut msg = BytesMut::new();
+                msg.put_u8(ServerOpcodes::OpenedChannel.into());
+                msg.put_u16_le(id);
+                msg.put_u16_le(name.len() as u16);
+                msg.put_slice(name.as_bytes());
+                msg.to_vec()
+            }
+            Self::OpenedPrivateChannel(name) => {
+                let mut msg = BytesMut::new();
+                msg.put_u8(ServerOpcodes::OpenedPrivateChannel.into());
+                msg.put_u16_le(name.len() as u16);
+                msg.put_slice(name.as_bytes());
+                msg.to_vec()
+            }
+            Self::OpenedShop(offers) => {
+                let mut msg = BytesMut::new();
+                msg.put_u8(ServerOpcodes::OpenedShop.into());
+                msg.put_u8(offers.len() as u8);
+                for ShopOffer {
+                    item,
+                    name,
+                    buy,
+                    sell,
+                } in offers.iter()
+                {
+                    msg.put_u16_le(*item);
+                    msg.put_u8(0x00); // subtype
+                    msg.put_u16_le(name.len() as u16);
+                    msg.put_slice(name.as_bytes());
+                    msg.put_u32_le(Items::try_from(*item).map_or(0, |item| item.weight()));
+                    msg.put_u32_le(*buy);
+                    msg.put_u32_le(*sell);
+                }
+                msg.to_vec()
+            }
+            Self::PlayerGoods { money, items } => {
+                let mut msg = BytesMut::new();
+                msg.put_u8(ServerOpcodes::PlayerGoods.into());
+                msg.put_u32_le(money.min(u64::from(u32::MAX)) as u32);
+                msg.put_u8(items.len() as u8);
+                for (item, count) in items.iter() {
+                    msg.put_u16_le(*item);
+                    msg.put_u8((*count).min(u32::from(u8::MAX)) as u8);
+                }
+                msg.to_vec()
+            }
+            Self::OpenedContainer { id, container } => {
+                let mut msg = BytesMut::new();
+                let items = container
+                    .container()
+                    .map_or(&[][..], |Container(items)| items.as_slice());
+                let name = container.name().map_or("", |Name(name)| name.as_str());
+                msg.put_u8(ServerOpcodes::OpenedContainer.into());
+                msg.put_u8(id);
+                msg.put_slice(&entity_to_bytes(&container));
+                msg.put_u16_le(name.len() as u16);
+                msg.put_slice(name.as_bytes());
+                msg.put_u8(items.len().max(Container::SLOTS) as u8);
+                msg.put_u8(0x00); // no parent container
+                msg.put_u8(items.len() as u8);
+                for item in items {
+                    msg.put_slice(&entity_to_bytes(item));
+                }
+                msg.to_vec()
+            }
+            Self::ClosedShop => {
+                let mut msg = BytesMut::new();
+                msg.put_u8(ServerOpcodes::ClosedShop.into());
+                msg.to_vec()
+            }
+            Self::TextMessage { class, text } => {
+                let mut msg = BytesMut::new();
+                msg.put_u8(ServerOpcodes::TextMessage.into());
+                msg.put_u8(class.into());
+                msg.put_u16_le(text.len() as u16);
+                msg.put_slice(text.as_bytes());
+                msg.to_vec()
+            }
+            Self::MagicEffect { position, effect } => {
+                let mut msg = BytesMut::new();
+                msg.put_u8(ServerOpcodes::MagicEffect.into());
+                msg.put_u16_le(position.x);
+                msg.put_u16_le(position.y);
+                msg.put_u8(position.z);
+                msg.put_u8(u8::from(effect) + 1);
+                msg.to_vec()
+            }
+            Self::DistanceShot { from, to, effect } => {
+                let mut msg = BytesMut::new();
+                msg.put_u8(ServerOpcodes::DistanceShot.into());
+                msg.put_u16_le(from.x);
+                msg.put_u16_le(from.y);
+                msg.put_u8(from.z);
+                msg.put_u16_le(to.x);
+                msg.put_u16_le(to.y);
+                msg.put_u8(to.z);
+                msg.put_u8(u8::from(effect) + 1);
+                msg.to_vec()
+            }
+            Self::AnimatedText {
+                position,
+                color,
+                text,
+            } => {
+                let mut msg = BytesMut::new();
+                msg.put_u8(ServerOpcodes::AnimatedText.into());
+                msg.put_u16_le(position.x);
+                msg.put_u16_le(position.y);
+                msg.put_u8(position.z);
+                msg.put_u8(color.into());
+                msg.put_u16_le(text.len() as u16);
+                msg.put_slice(text.as_bytes());
+                msg.to_vec()
+            }
             // Self::Raw(msg) => {
             //     let mut msg2 = BytesMut::new();
             //     msg2.put_u8(opcode);
@@ -435,3 +780,168 @@ impl Payload {
         }
     }
 }
+
+impl Payload {
+    /// The entity as it is now and where clients see it, none when it is beyond the things they see.
+    pub fn added_entity(world: &World, id: EntityId) -> Option<Self> {
+        Some(Self::AddedEntity {
+            position: client_position(&world.position(id)?)?,
+            entity: world.entity(id)?.clone(),
+        })
+    }
+
+    pub fn stats(entity: &Entity) -> Option<Self> {
+        let Health { value, max } = entity.health()?;
+        let level = entity.level()?;
+        let experience = entity.experience()?;
+        let magic_level = entity.magic_level()?;
+        let mana = entity.mana()?;
+        let Soul(soul) = entity.soul()?;
+        let Stamina(stamina) = entity.stamina()?;
+        Some(Self::Stats {
+            health: *value,
+            health_max: *max,
+            capacity: entity.capacity()?.free(entity.inventory()),
+            experience: experience.0 as u32,
+            level: level.0,
+            level_progress: level.progress(experience),
+            mana: mana.value,
+            mana_max: mana.max,
+            magic: magic_level.level as u8,
+            magic_progress: magic_level.progress(),
+            soul: *soul,
+            stamina: *stamina,
+        })
+    }
+
+    /// Money and items the player could sell, carried stacks added up.
+    pub fn player_goods(entity: &Entity) -> Option<Self> {
+        let inventory = entity.inventory()?;
+        let items = inventory
+            .0
+            .values()
+            .filter_map(|entity| entity.item())
+            .map(|Item(item)| *item)
+            .unique()
+            .sorted()
+            .filter_map(|item| Some((item, inventory.count(Items::try_from(item).ok()?))))
+            .collect();
+        Some(Self::PlayerGoods {
+            money: shops::money(inventory),
+            items,
+        })
+    }
+
+    pub fn icons(entity: &Entity) -> Self {
+        Self::Icons(
+            entity
+                .conditions()
+                .map(|Conditions(conditions)| conditions.keys().copied().collect())
+                .unwrap_or_default(),
+        )
+    }
+
+    pub fn skills(entity: &Entity) -> Option<Self> {
+        let skills = entity.skills()?;
+        Some(Self::Skills(
+            SkillType::ALL
+                .into_iter()
+                .map(|r#type| {
+                    let skill = skills.get(r#type);
+                    SkillEntry {
+                        r#type,
+                        level: skill.level as u8,
+                        progress: skill.progress(r#type),
+                    }
+                })
+                .collect(),
+        ))
+    }
+}
+
+fn entity_to_bytes(entity: &Entity) -> Vec<u8> {
+    let mut msg = BytesMut::new();
+    if let Some(CreatureId(id)) = entity.creature_id() {
+        // TODO: handle known creature
+        msg.put_u16_le(0x61);
+        msg.put_u32_le(0x00);
+        msg.put_u32_le(*id);
+        let Name(name) = entity.name().unwrap();
+        msg.put_u16_le(name.len() as u16);
+        msg.put_slice(name.as_bytes());
+        // TODO: handle creature health hidden case
+        msg.put_u8(entity.health().unwrap().percent());
+        let Direction(direction) = entity.direction().unwrap();
+        msg.put_u8(*direction as u8);
+        let Outfit {
+            r#type,
+            head,
+            body,
+            legs,
+            feet,
+            addons,
+        } = entity.outfit().unwrap();
+        msg.put_u16_le(*r#type);
+        msg.put_u8(*head);
+        msg.put_u8(*body);
+        msg.put_u8(*legs);
+        msg.put_u8(*feet);
+        msg.put_u8(*addons);
+        let LightInfo { level, color } = entity.light_info().unwrap();
+        msg.put_u8(*level); // TODO: send 0xFF when "access player" (gm, god etc.)
+        msg.put_u8(*color);
+        let Speed(speed) = entity.speed().unwrap();
+        msg.put_u16_le(*speed);
+        let Skull(skull) = entity.skull().unwrap();
+        msg.put_u8(*skull as u8);
+        let PartyShield(party_shield) = entity.party_shield().unwrap();
+        msg.put_u8(*party_shield as u8);
+    } else if let Some(Item(id)) = entity.item() {
+        msg.put_u16_le(*id);
+        if Items::try_from(*id).is_ok_and(|item| item.is_stackable()) {
+            msg.put_u8(entity.count().map_or(1, |Count(count)| *count));
+        }
+    }
+    msg.to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn things_beyond_the_visible_ones_have_no_client_position() {
+        let mut world = World::new();
+        let position = Position(100, 100, 7);
+        world.add_tile(position.clone(), Vec::new());
+        let ids = (0..12)
+            .map(|id| world.add_entity(&position, entity![Item(id)]).unwrap())
+            .collect::<Vec<_>>();
+        // the latest item is on top of the pile, so the first two are beyond the visible ones
+        for (index, id) in ids.iter().rev().enumerate() {
+            let position = world.position(*id).unwrap();
+            assert_eq!(position.stack_pos, Some(index as u16));
+            let visible = index < usize::from(MAX_VISIBLE_THINGS);
+            assert_eq!(client_position(&position).is_some(), visible);
+        }
+    }
+
+    #[test]
+    fn clients_address_only_the_visible_things() {
+        let mut world = World::new();
+        let position = Position(100, 100, 7);
+        world.add_tile(
+            position.clone(),
+            (0..12).map(|id| entity![Item(id)]).collect(),
+        );
+        for stack_pos in 0..12u8 {
+            let addressed = server_stack_pos(stack_pos)
+                .and_then(|stack_pos| world.entity_at(&position.clone().stack_pos(Some(stack_pos))))
+                .and_then(|id| world.position(id))
+                .and_then(|position| client_position(&position));
+            let expected = (u16::from(stack_pos) < MAX_VISIBLE_THINGS)
+                .then(|| position.clone().stack_pos(Some(stack_pos.into())));
+            assert_eq!(addressed, expected);
+        }
+    }
+}
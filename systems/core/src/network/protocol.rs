@@ -1,7 +1,7 @@
-use super::super::attributes::{Item, Player};
+use super::super::attributes::Item;
 use super::{
     definitions::{AccountName, ClientOpcodes, Packet},
-    payload::Payload,
+    payload::{server_stack_pos, Payload},
     rsa::Rsa,
     xtea::Xtea,
 };
@@ -133,24 +133,35 @@ impl Decoder for Protocol {
                     password,
                 }
             }
+            Some(ClientOpcodes::Logout) => Payload::Logout,
             Some(ClientOpcodes::Ping) => Payload::Ping,
             Some(ClientOpcodes::PingBack) => Payload::PingBack,
             Some(ClientOpcodes::MoveNorth) => Payload::Move {
-                player: Player(1),
                 direction: Direction(Directions::North),
             },
             Some(ClientOpcodes::MoveEast) => Payload::Move {
-                player: Player(1),
                 direction: Direction(Directions::East),
             },
             Some(ClientOpcodes::MoveSouth) => Payload::Move {
-                player: Player(1),
                 direction: Direction(Directions::South),
             },
             Some(ClientOpcodes::MoveWest) => Payload::Move {
-                player: Player(1),
                 direction: Direction(Directions::West),
             },
+            Some(ClientOpcodes::Purchase) => {
+                let item = msg.get_u16_le();
+                msg.advance(1); // subtype
+                let amount = msg.get_u8();
+                // bought items always go to the inventory, so the capacity is never ignored
+                Payload::Purchase { item, amount }
+            }
+            Some(ClientOpcodes::Sell) => {
+                let item = msg.get_u16_le();
+                msg.advance(1); // subtype
+                let amount = msg.get_u8();
+                Payload::Sell { item, amount }
+            }
+            Some(ClientOpcodes::CloseShop) => Payload::CloseShop,
             Some(ClientOpcodes::UseItem) => {
                 let x = msg.get_u16_le();
                 let y = msg.get_u16_le();
@@ -163,11 +174,95 @@ impl Decoder for Protocol {
                         x,
                         y,
                         z,
-                        stack_pos: Some(stack_pos.into()),
+                        stack_pos: server_stack_pos(stack_pos),
                     },
                     item: Item(id),
                 }
             }
+            Some(ClientOpcodes::UseItemOn) => {
+                let from_x = msg.get_u16_le();
+                let from_y = msg.get_u16_le();
+                let from_z = msg.get_u8();
+                let from_id = msg.get_u16_le();
+                let from_stack_pos = msg.get_u8();
+                let to_x = msg.get_u16_le();
+                let to_y = msg.get_u16_le();
+                let to_z = msg.get_u8();
+                let to_id = msg.get_u16_le();
+                let to_stack_pos = msg.get_u8();
+                Payload::UseItemOn {
+                    from: Position {
+                        x: from_x,
+                        y: from_y,
+                        z: from_z,
+                        stack_pos: server_stack_pos(from_stack_pos),
+                    },
+                    from_item: Item(from_id),
+                    to: Position {
+                        x: to_x,
+                        y: to_y,
+                        z: to_z,
+                        stack_pos: server_stack_pos(to_stack_pos),
+                    },
+                    to_item: Item(to_id),
+                }
+            }
+            Some(ClientOpcodes::Talk) => {
+                let class = msg.get_u8();
+                match SpeakClasses::try_from(class) {
+                    Ok(class) => {
+                        let receiver = match class {
+                            SpeakClasses::Private | SpeakClasses::PrivateRed => {
+                                let receiver_length = msg.get_u16_le();
+                                Some(
+                                    String::from_utf8_lossy(
+                                        msg.copy_to_bytes(receiver_length.into()).chunk(),
+                                    )
+                                    .to_string(),
+                                )
+                            }
+                            _ => None,
+                        };
+                        let channel = match class {
+                            SpeakClasses::Channel
+                            | SpeakClasses::ChannelRed
+                            | SpeakClasses::ChannelOrange => Some(msg.get_u16_le()),
+                            _ => None,
+                        };
+                        let text_length = msg.get_u16_le();
+                        let text =
+                            String::from_utf8_lossy(msg.copy_to_bytes(text_length.into()).chunk())
+                                .to_string();
+                        Payload::Talk {
+                            class,
+                            receiver,
+                            channel,
+                            text,
+                        }
+                    }
+                    Err(_) => Payload::Raw(msg),
+                }
+            }
+            Some(ClientOpcodes::RequestChannels) => Payload::RequestChannels,
+            Some(ClientOpcodes::OpenChannel) => Payload::OpenChannel {
+                channel: msg.get_u16_le(),
+            },
+            Some(ClientOpcodes::CloseChannel) => Payload::CloseChannel {
+                channel: msg.get_u16_le(),
+            },
+            Some(ClientOpcodes::OpenPrivateChannel) => {
+                let name_length = msg.get_u16_le();
+                let name = String::from_utf8_lossy(msg.copy_to_bytes(name_length.into()).chunk())
+                    .to_string();
+                Payload::OpenPrivateChannel { name }
+            }
+            Some(ClientOpcodes::Attack) => Payload::Attack {
+                target: msg.get_u32_le(),
+            },
+            Some(ClientOpcodes::Follow) => Payload::Follow {
+                target: msg.get_u32_le(),
+            },
+            Some(ClientOpcodes::Cancel) => Payload::Cancel,
             _ => Payload::Raw(msg),
         };
 
@@ -0,0 +1,364 @@
+use crate::{combat, data, geometry, prelude::*};
+use indexmap::IndexMap;
+use skyless_core::{prelude::*, World};
+use std::cmp::Reverse;
+use std::time::{Duration, Instant};
+use tokio::time;
+
+const NPCS_PATH: &str = "data/npcs";
+
+const THINK_INTERVAL: Duration = Duration::from_secs(1);
+// NPCs only listen to players standing close to them
+const TALK_RANGE: u16 = 3;
+// the conversation ends when the player said nothing for that long
+const FOCUS_TIMEOUT: Duration = Duration::from_secs(60);
+// chance in percent to take a step while nobody is talked to
+const WANDER_CHANCE: u8 = 20;
+
+const GREETINGS: [&str; 2] = ["hi", "hello"];
+const FAREWELLS: [&str; 2] = ["bye", "farewell"];
+const BUSY: &str = "I'll be with you in a moment, {player}.";
+
+/// Makes the NPC say `text` wherever it stands, scripts answer players through it.
+#[event]
+pub struct NpcSayEvent {
+    pub npc: CreatureId,
+    pub text: String,
+}
+
+/// Something said by the player the NPC is talking to, for scripts to react on.
+#[event]
+pub struct DialogueEvent {
+    pub npc: CreatureId,
+    pub player: CreatureId,
+    pub text: String,
+}
+
+/// Whether the words of `keyword` are said one after another in `text`, regardless of case.
+pub fn says(text: &str, keyword: &str) -> bool {
+    let words: Vec<&str> = text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect();
+    let keyword: Vec<&str> = keyword.split_whitespace().collect();
+    !keyword.is_empty()
+        && words.windows(keyword.len()).any(|window| {
+            window
+                .iter()
+                .zip(&keyword)
+                .all(|(word, keyword)| word.eq_ignore_ascii_case(keyword))
+        })
+}
+
+/// Answer to the longest keyword said in `text`, the first one of the data file among equally long ones.
+fn reply<'a>(keywords: &'a IndexMap<String, String>, text: &str) -> Option<&'a String> {
+    keywords
+        .iter()
+        .filter(|(keyword, _)| says(text, keyword))
+        .min_by_key(|(keyword, _)| Reverse(keyword.len()))
+        .map(|(_, reply)| reply)
+}
+
+fn answer(text: &str, player_name: &str) -> String {
+    text.replace("{player}", player_name)
+}
+
+/// What the NPC says and what is heard by scripts, sent after the player's own words reached the clients.
+fn conversation(
+    npc: CreatureId,
+    answers: Vec<String>,
+    dialogue: Option<DialogueEvent>,
+) -> TaskType {
+    Box::pin(stream! {
+        for text in answers {
+            yield Some(Arc::new(NpcSayEvent { npc: npc.clone(), text }) as EventType);
+        }
+        if let Some(dialogue) = dialogue {
+            yield Some(Arc::new(dialogue) as EventType);
+        }
+    })
+}
+
+fn npc(npc_type: &NpcType, id: CreatureId) -> Entity {
+    let outfit = npc_type.outfit.clone();
+    entity![
+        id,
+        Npc(npc_type.name.clone()),
+        Name(npc_type.name.clone()),
+        Health {
+            value: 100,
+            max: 100
+        },
+        Direction(Directions::South),
+        outfit,
+        LightInfo::NONE,
+        Speed(npc_type.speed),
+        Skull(Skulls::None),
+        PartyShield(PartyShields::None)
+    ]
+}
+
+fn think(
+    world: &World,
+    npc_types: &NpcTypes,
+    id: EntityId,
+    entity: &Entity,
+) -> Option<(CommandsType, Option<TaskType>)> {
+    let position = &world.position(id)?.stack_pos(None);
+    let Npc(name) = entity.npc()?;
+    let npc_type = npc_types.0.get(name)?;
+    let creature = entity.creature_id()?;
+    let now = Instant::now();
+    let mut commands = Vec::new();
+
+    if let Some(focus) = entity.focus() {
+        let player = world.find_creature(&focus.player);
+        let near = player
+            .and_then(|player| world.position(player))
+            .is_some_and(|player_position| {
+                player_position.z == position.z
+                    && geometry::distance(position, &player_position) <= TALK_RANGE
+            });
+        if near && now.duration_since(focus.last) < FOCUS_TIMEOUT {
+            return Some((commands, None));
+        }
+        commands.push(Box::new(RemoveEntityAttributeCommand {
+            entity: id,
+            attribute: Box::new(focus.clone()),
+        }) as CommandType);
+        let task =
+            player
+                .and_then(|player| world.entity(player)?.name())
+                .map(|Name(player_name)| {
+                    conversation(
+                        creature.clone(),
+                        vec![answer(&npc_type.farewell, player_name)],
+                        None,
+                    )
+                });
+        return Some((commands, task));
+    }
+
+    // NPCs stroll around their place, without leaving it further than their radius
+    let walk_ready = entity
+        .walking()
+        .is_none_or(|Walking { until }| *until <= now);
+    if !walk_ready || fastrand::u8(0..100) >= WANDER_CHANCE {
+        return Some((commands, None));
+    }
+    let (x, y, z) = npc_type.position;
+    let home = Position(x, y, z);
+    let neighbours: Vec<Position> = geometry::in_range(position, 1)
+        .filter(|neighbour| geometry::distance(neighbour, &home) <= npc_type.radius)
+        .filter(|neighbour| geometry::is_walkable(world, neighbour))
+        .collect();
+    if let Some(to) = fastrand::choice(neighbours) {
+        commands.push(Box::new(SetEntityAttributeCommand {
+            entity: id,
+            attribute: Box::new(Walking {
+                until: now + combat::step_duration(entity),
+            }),
+        }) as CommandType);
+        commands.push(Box::new(EmitEventCommand(Arc::new(MoveEvent {
+            from: position.clone(),
+            to,
+            creature: creature.clone(),
+        }))) as CommandType);
+    }
+    Some((commands, None))
+}
+
+system! {
+    #[event]
+    pub struct NpcThinkEvent;
+
+    task! {
+        let mut interval = time::interval(THINK_INTERVAL);
+
+        loop {
+            interval.tick().await;
+            yield Some(Arc::new(NpcThinkEvent) as EventType);
+        }
+    }
+
+    // the map has to be loaded before, NPCs can only be placed on existing tiles
+    #[effect(SystemsLoadedEvent)]
+    fn load_npcs(_: EventType, attributes: GameAttributesType, world: WorldType) -> EffectResultType {
+        let mut creature_ids = attributes.lock().unwrap().creature_ids().cloned().unwrap_or_default();
+        let world = world.lock().unwrap();
+        let mut commands = Vec::new();
+        let mut npc_types = HashMap::new();
+
+        for npc_type in data::load_all::<NpcType>(NPCS_PATH) {
+            let (x, y, z) = npc_type.position;
+            let position = Position(x, y, z);
+            if !geometry::is_walkable(&world, &position) {
+                println!("Could not place NPC {} at {:?}", npc_type.name, npc_type.position);
+                continue;
+            }
+            commands.push(Box::new(AddEntityCommand {
+                position,
+                entity: npc(&npc_type, creature_ids.allocate()),
+            }) as CommandType);
+            npc_types.insert(npc_type.name.clone(), npc_type);
+        }
+
+        commands.push(Box::new(SetGameAttributeCommand(Box::new(NpcTypes(npc_types)))) as CommandType);
+        commands.push(Box::new(SetGameAttributeCommand(Box::new(creature_ids))) as CommandType);
+        Some((commands, Vec::new()))
+    }
+
+    #[effect(SpeakEvent)]
+    fn handle_npc_speech(event: EventType, attributes: GameAttributesType, world: WorldType) -> EffectResultType {
+        let SpeakEvent { player, position, class, text } = event.as_any().downcast_ref::<SpeakEvent>().unwrap();
+        let game_attributes = attributes.lock().unwrap();
+        let world = world.lock().unwrap();
+        let mut commands = Vec::new();
+        let mut tasks = Vec::new();
+
+        // NPCs do not listen to each other, nor to anything but plain talk
+        let (Some(player), SpeakClasses::Say) = (player, class) else {
+            return Some((commands, tasks));
+        };
+        let Some(NpcTypes(npc_types)) = game_attributes.npc_types() else {
+            return Some((commands, tasks));
+        };
+        let Some(Name(player_name)) = world.find_creature(player).and_then(|player| world.entity(player)?.name()) else {
+            return Some((commands, tasks));
+        };
+        let now = Instant::now();
+
+        for tile_position in geometry::in_range(position, TALK_RANGE) {
+            for (id, entity) in world.entities(&tile_position) {
+                let (Some(Npc(name)), Some(creature)) = (entity.npc(), entity.creature_id()) else {
+                    continue;
+                };
+                let Some(npc_type) = npc_types.get(name) else {
+                    continue;
+                };
+                let focus = entity.focus();
+
+                if focus.is_none_or(|focus| focus.player != *player) {
+                    if GREETINGS.iter().any(|greeting| says(text, greeting)) {
+                        let reply = match focus {
+                            Some(_) => BUSY,
+                            None => {
+                                commands.push(Box::new(SetEntityAttributeCommand {
+                                    entity: id,
+                                    attribute: Box::new(Focus { player: player.clone(), last: now }),
+                                }) as CommandType);
+                                &npc_type.greeting
+                            }
+                        };
+                        tasks.push(conversation(creature.clone(), vec![answer(reply, player_name)], None));
+                    }
+                    continue;
+                }
+
+                if FAREWELLS.iter().any(|farewell| says(text, farewell)) {
+                    commands.push(Box::new(RemoveEntityAttributeCommand {
+                        entity: id,
+                        attribute: Box::new(focus.unwrap().clone()),
+                    }) as CommandType);
+                    tasks.push(conversation(creature.clone(), vec![answer(&npc_type.farewell, player_name)], None));
+                    continue;
+                }
+
+                commands.push(Box::new(SetEntityAttributeCommand {
+                    entity: id,
+                    attribute: Box::new(Focus { player: player.clone(), last: now }),
+                }) as CommandType);
+                let answers = reply(&npc_type.keywords, text).map(|reply| answer(reply, player_name)).into_iter().collect();
+                let dialogue = DialogueEvent {
+                    npc: creature.clone(),
+                    player: player.clone(),
+                    text: text.clone(),
+                };
+                tasks.push(conversation(creature.clone(), answers, Some(dialogue)));
+            }
+        }
+
+        Some((commands, tasks))
+    }
+
+    #[effect(NpcSayEvent)]
+    fn handle_npc_say(event: EventType, _: GameAttributesType, world: WorldType) -> EffectResultType {
+        let NpcSayEvent { npc, text } = event.as_any().downcast_ref::<NpcSayEvent>().unwrap();
+        let world = world.lock().unwrap();
+        let commands = world
+            .find_creature(npc)
+            .and_then(|id| world.position(id))
+            .map(|position| Box::new(EmitEventCommand(Arc::new(SpeakEvent {
+                player: None,
+                position,
+                class: SpeakClasses::Say,
+                text: text.clone(),
+            }))) as CommandType)
+            .into_iter()
+            .collect();
+        Some((commands, Vec::new()))
+    }
+
+    #[effect(NpcThinkEvent)]
+    fn handle_npc_think(_: EventType, attributes: GameAttributesType, world: WorldType) -> EffectResultType {
+        let game_attributes = attributes.lock().unwrap();
+        let world = world.lock().unwrap();
+        let mut commands = Vec::new();
+        let mut tasks = Vec::new();
+        let Some(npc_types) = game_attributes.npc_types() else {
+            return Some((commands, tasks));
+        };
+        for (_, id) in world.creatures() {
+            let Some(entity) = world.entity(id).filter(|entity| entity.npc().is_some()) else {
+                continue;
+            };
+            if let Some((mut npc_commands, task)) = think(&world, npc_types, id, entity) {
+                commands.append(&mut npc_commands);
+                tasks.extend(task);
+            }
+        }
+        Some((commands, tasks))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keywords(pairs: &[(&str, &str)]) -> IndexMap<String, String> {
+        pairs
+            .iter()
+            .map(|(keyword, reply)| (keyword.to_string(), reply.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn first_keyword_of_the_file_answers_among_equally_long_ones() {
+        let keywords = keywords(&[
+            ("trade", "offers"),
+            ("offer", "also offers"),
+            ("job", "blacksmith"),
+        ]);
+        assert_eq!(reply(&keywords, "trade or offer?").unwrap(), "offers");
+        assert_eq!(reply(&keywords, "your offer and trade").unwrap(), "offers");
+        assert_eq!(reply(&keywords, "what is your jobs"), None);
+    }
+
+    #[test]
+    fn keywords_keep_the_order_of_the_data_file() {
+        let keywords: IndexMap<String, String> =
+            toml::from_str("trade = \"offers\"\noffer = \"also offers\"").unwrap();
+        assert_eq!(keywords.keys().collect::<Vec<_>>(), ["trade", "offer"]);
+    }
+
+    #[test]
+    fn longest_keyword_answers() {
+        let keywords = keywords(&[
+            ("sword", "swords"),
+            ("magic sword", "rare"),
+            ("job", "blacksmith"),
+        ]);
+        assert_eq!(reply(&keywords, "a magic sword please").unwrap(), "rare");
+        assert_eq!(reply(&keywords, "a sword please").unwrap(), "swords");
+    }
+}
@@ -0,0 +1,1180 @@
+use crate::{combat, map, prelude::*};
+use argon2::{
+    password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+    Argon2,
+};
+use itertools::{EitherOrBoth, Itertools};
+use rusqlite::{params, Connection, OptionalExtension, Row, Transaction};
+use skyless_core::{prelude::*, World};
+use std::collections::HashSet;
+use std::env::var;
+use std::future::Future;
+use std::time::Duration;
+use tokio::{
+    sync::{
+        mpsc::{self, UnboundedReceiver, UnboundedSender},
+        oneshot,
+    },
+    task, time,
+};
+
+const DATABASE_PATH: &str = "data/skyless.db";
+
+const SAVE_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+// an empty database gets the account set by SEED_ACCOUNT and SEED_PASSWORD, if any, holding a
+// single character standing in the temple
+const SEED_CHARACTER: &str = "Skyless";
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS accounts (
+        id INTEGER PRIMARY KEY,
+        password_hash TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS characters (
+        name TEXT PRIMARY KEY COLLATE NOCASE,
+        account INTEGER NOT NULL REFERENCES accounts (id),
+        x INTEGER NOT NULL,
+        y INTEGER NOT NULL,
+        z INTEGER NOT NULL,
+        direction INTEGER NOT NULL,
+        health INTEGER NOT NULL,
+        max_health INTEGER NOT NULL,
+        mana INTEGER NOT NULL,
+        max_mana INTEGER NOT NULL,
+        soul INTEGER NOT NULL,
+        capacity INTEGER NOT NULL,
+        stamina INTEGER NOT NULL,
+        level INTEGER NOT NULL,
+        experience INTEGER NOT NULL,
+        magic_level INTEGER NOT NULL,
+        mana_spent INTEGER NOT NULL,
+        speed INTEGER NOT NULL,
+        outfit_type INTEGER NOT NULL,
+        outfit_head INTEGER NOT NULL,
+        outfit_body INTEGER NOT NULL,
+        outfit_legs INTEGER NOT NULL,
+        outfit_feet INTEGER NOT NULL,
+        outfit_addons INTEGER NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS character_skills (
+        character TEXT NOT NULL REFERENCES characters (name),
+        skill INTEGER NOT NULL,
+        level INTEGER NOT NULL,
+        tries INTEGER NOT NULL,
+        PRIMARY KEY (character, skill)
+    );
+    CREATE TABLE IF NOT EXISTS character_items (
+        character TEXT NOT NULL REFERENCES characters (name),
+        item_index INTEGER NOT NULL,
+        parent INTEGER,
+        slot INTEGER NOT NULL,
+        item INTEGER NOT NULL,
+        count INTEGER,
+        action_id INTEGER,
+        decay INTEGER,
+        container INTEGER NOT NULL,
+        PRIMARY KEY (character, item_index)
+    );
+    CREATE TABLE IF NOT EXISTS character_storage (
+        character TEXT NOT NULL REFERENCES characters (name),
+        key INTEGER NOT NULL,
+        value INTEGER NOT NULL,
+        PRIMARY KEY (character, key)
+    );
+    CREATE TABLE IF NOT EXISTS tile_items (
+        x INTEGER NOT NULL,
+        y INTEGER NOT NULL,
+        z INTEGER NOT NULL,
+        item_index INTEGER NOT NULL,
+        parent INTEGER,
+        slot INTEGER NOT NULL,
+        item INTEGER NOT NULL,
+        count INTEGER,
+        action_id INTEGER,
+        decay INTEGER,
+        container INTEGER NOT NULL,
+        PRIMARY KEY (x, y, z, item_index)
+    );
+";
+
+// the items of a character or a tile are numbered by item_index, the ones inside a container have
+// the index of the container as parent and their place in it as slot, the others their inventory
+// slot or their place on the tile
+const ITEM_COLUMNS: &str = "item_index, parent, slot, item, count, action_id, decay, container";
+
+/// An item as it is written to the database, along with the items inside it.
+#[derive(Debug, Clone, PartialEq)]
+struct SavedItem {
+    id: u16,
+    count: Option<u8>,
+    action_id: Option<u16>,
+    // the milliseconds left before decaying, the time stands still while the server is down
+    decay: Option<u64>,
+    // none for an item that is no container
+    contents: Option<Vec<SavedItem>>,
+}
+
+// the item index, the index of the container holding the item and the slot of the item
+type ItemRow<T> = (usize, Option<usize>, usize, T);
+
+fn open() -> rusqlite::Result<Connection> {
+    let connection = Connection::open(DATABASE_PATH)?;
+    connection.execute_batch(SCHEMA)?;
+    Ok(connection)
+}
+
+fn item(id: u16, count: Option<u8>) -> Entity {
+    let mut item = entity![Item(id)];
+    if let Some(count) = count.map(Count) {
+        item.attributes
+            .insert(count.as_name().to_string(), Box::new(count));
+    }
+    item
+}
+
+fn as_saved(entity: &Entity) -> Option<SavedItem> {
+    Some(SavedItem {
+        id: entity.item()?.0,
+        count: entity.count().map(|Count(count)| *count),
+        action_id: entity.action_id().map(|ActionId(action_id)| *action_id),
+        decay: entity
+            .decay()
+            .map(|decay| decay.remaining().as_millis() as u64),
+        contents: entity
+            .container()
+            .map(|Container(items)| items.iter().filter_map(as_saved).collect()),
+    })
+}
+
+fn restored(saved: &SavedItem) -> Entity {
+    let mut item = item(saved.id, saved.count);
+    let attributes = [
+        saved
+            .action_id
+            .map(|action_id| Box::new(ActionId(action_id)) as Box<dyn Attribute>),
+        saved.decay.map(|decay| {
+            Box::new(Decay::after(Duration::from_millis(decay))) as Box<dyn Attribute>
+        }),
+        saved.contents.as_ref().map(|contents| {
+            Box::new(Container(contents.iter().map(restored).collect())) as Box<dyn Attribute>
+        }),
+    ];
+    for attribute in attributes.into_iter().flatten() {
+        item.attributes
+            .insert(attribute.as_name().to_string(), attribute);
+    }
+    item
+}
+
+/// Rows of the items and of everything inside them, each container comes before its contents.
+fn item_rows<'a>(
+    items: impl IntoIterator<Item = (usize, &'a SavedItem)>,
+) -> Vec<ItemRow<&'a SavedItem>> {
+    let mut rows = Vec::new();
+    let mut pending: Vec<(Option<usize>, usize, &SavedItem)> = items
+        .into_iter()
+        .map(|(slot, item)| (None, slot, item))
+        .collect();
+    pending.reverse();
+    while let Some((parent, slot, item)) = pending.pop() {
+        let index = rows.len();
+        rows.push((index, parent, slot, item));
+        let contents = item
+            .contents
+            .as_deref()
+            .unwrap_or_default()
+            .iter()
+            .enumerate();
+        pending.extend(contents.rev().map(|(slot, item)| (Some(index), slot, item)));
+    }
+    rows
+}
+
+fn item_row(row: &Row) -> rusqlite::Result<ItemRow<SavedItem>> {
+    let item = SavedItem {
+        id: row.get("item")?,
+        count: row.get("count")?,
+        action_id: row.get("action_id")?,
+        decay: row.get("decay")?,
+        contents: row.get::<_, bool>("container")?.then(Vec::new),
+    };
+    Ok((
+        row.get("item_index")?,
+        row.get("parent")?,
+        row.get("slot")?,
+        item,
+    ))
+}
+
+/// Puts the items of the rows back into their containers, along with the slot of the outermost ones.
+fn nested(rows: Vec<ItemRow<SavedItem>>) -> Vec<(usize, SavedItem)> {
+    let mut held: HashMap<usize, Vec<(usize, SavedItem)>> = HashMap::new();
+    let mut items = Vec::new();
+    // going from the last index, the contents of a container are all there once it is reached
+    for (index, parent, slot, mut item) in
+        rows.into_iter().sorted_by_key(|(index, ..)| *index).rev()
+    {
+        if let (Some(contents), Some(mut inside)) = (item.contents.as_mut(), held.remove(&index)) {
+            inside.sort_by_key(|(slot, _)| *slot);
+            contents.extend(inside.into_iter().map(|(_, item)| item));
+        }
+        match parent {
+            Some(parent) => held.entry(parent).or_default().push((slot, item)),
+            None => items.push((slot, item)),
+        }
+    }
+    items.sort_by_key(|(slot, _)| *slot);
+    items
+}
+
+/// Character as it enters the game for the first time.
+fn new_character(name: &str, account: u32) -> Entity {
+    let name = Name(name.into());
+    let skills = Skills(
+        SkillType::ALL
+            .into_iter()
+            .map(|skill| (skill, Skill::default()))
+            .collect(),
+    );
+    let inventory = Inventory(HashMap::from([
+        (
+            InventorySlot::Backpack,
+            item(Items::PlatinumCoin.into(), Some(10)),
+        ),
+        (
+            InventorySlot::Right,
+            entity![Item(Items::Key.into()), ActionId(1001)],
+        ),
+        (InventorySlot::Left, item(Items::Bow.into(), None)),
+        (InventorySlot::Ammo, item(Items::Arrow.into(), Some(50))),
+    ]));
+    entity![
+        Player,
+        Account(account),
+        name,
+        Health {
+            value: 150,
+            max: 150
+        },
+        Mana { value: 50, max: 50 },
+        Soul(Soul::MAX),
+        Capacity(40000),
+        Stamina(56 * 60),
+        Direction(Directions::South),
+        Outfit {
+            r#type: 128,
+            head: 78,
+            body: 69,
+            legs: 58,
+            feet: 76,
+            addons: 0
+        },
+        Speed(220),
+        Level(1),
+        Experience(0),
+        MagicLevel {
+            level: 0,
+            mana_spent: 0
+        },
+        skills,
+        Storage(HashMap::new()),
+        inventory
+    ]
+}
+
+/// Salted hash of the password, in the PHC string format that holds the salt and parameters too.
+fn hash(password: &str) -> rusqlite::Result<String> {
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|error| rusqlite::Error::ToSqlConversionFailure(Box::new(error)))
+}
+
+fn verify(password: &str, hash: &str) -> bool {
+    PasswordHash::new(hash).is_ok_and(|hash| {
+        Argon2::default()
+            .verify_password(password.as_bytes(), &hash)
+            .is_ok()
+    })
+}
+
+fn seed_account() -> Option<(u32, String)> {
+    Some((
+        var("SEED_ACCOUNT").ok()?.parse().ok()?,
+        var("SEED_PASSWORD").ok()?,
+    ))
+}
+
+fn create_seed_account(connection: &mut Connection) -> rusqlite::Result<()> {
+    let Some((account, password)) = seed_account() else {
+        return Ok(());
+    };
+    let accounts: u32 =
+        connection.query_row("SELECT COUNT(*) FROM accounts", [], |row| row.get(0))?;
+    if accounts > 0 {
+        return Ok(());
+    }
+    let transaction = connection.transaction()?;
+    transaction.execute(
+        "INSERT INTO accounts (id, password_hash) VALUES (?1, ?2)",
+        params![account, hash(&password)?],
+    )?;
+    write_character(
+        &transaction,
+        &new_character(SEED_CHARACTER, account),
+        &combat::TEMPLE,
+    )?;
+    transaction.commit()?;
+    println!(
+        "Created account {} with character {}",
+        account, SEED_CHARACTER
+    );
+    Ok(())
+}
+
+fn authenticate(connection: &Connection, account: u32, password: &str) -> rusqlite::Result<bool> {
+    let hash = connection
+        .query_row(
+            "SELECT password_hash FROM accounts WHERE id = ?1",
+            [account],
+            |row| row.get::<_, String>(0),
+        )
+        .optional()?;
+    Ok(hash.is_some_and(|hash| verify(password, &hash)))
+}
+
+fn read_names(connection: &Connection) -> rusqlite::Result<HashSet<String>> {
+    let mut statement = connection.prepare("SELECT name FROM characters")?;
+    let names = statement
+        .query_map([], |row| row.get::<_, String>(0))?
+        .map(|name| name.map(|name| name.to_lowercase()))
+        .collect();
+    names
+}
+
+fn read_characters(
+    connection: &Connection,
+    account: u32,
+    password: &str,
+) -> rusqlite::Result<Option<Vec<String>>> {
+    if !authenticate(connection, account, password)? {
+        return Ok(None);
+    }
+    let mut statement =
+        connection.prepare("SELECT name FROM characters WHERE account = ?1 ORDER BY name")?;
+    let names = statement
+        .query_map([account], |row| row.get(0))?
+        .collect::<rusqlite::Result<Vec<String>>>()?;
+    Ok(Some(names))
+}
+
+fn character(row: &Row) -> rusqlite::Result<(Entity, Position)> {
+    let name = Name(row.get("name")?);
+    let direction = Direction(
+        Directions::try_from(row.get::<_, u8>("direction")?).unwrap_or(Directions::South),
+    );
+    let outfit = Outfit {
+        r#type: row.get("outfit_type")?,
+        head: row.get("outfit_head")?,
+        body: row.get("outfit_body")?,
+        legs: row.get("outfit_legs")?,
+        feet: row.get("outfit_feet")?,
+        addons: row.get("outfit_addons")?,
+    };
+    let character = entity![
+        Player,
+        Account(row.get("account")?),
+        name,
+        Health {
+            value: row.get("health")?,
+            max: row.get("max_health")?
+        },
+        Mana {
+            value: row.get("mana")?,
+            max: row.get("max_mana")?
+        },
+        Soul(row.get("soul")?),
+        Capacity(row.get("capacity")?),
+        Stamina(row.get("stamina")?),
+        direction,
+        outfit,
+        LightInfo::NONE,
+        Speed(row.get("speed")?),
+        Skull(Skulls::None),
+        PartyShield(PartyShields::None),
+        Level(row.get("level")?),
+        Experience(row.get("experience")?),
+        MagicLevel {
+            level: row.get("magic_level")?,
+            mana_spent: row.get("mana_spent")?
+        },
+        AttackSpeed(Duration::from_millis(2000))
+    ];
+    Ok((
+        character,
+        Position(row.get("x")?, row.get("y")?, row.get("z")?),
+    ))
+}
+
+fn read_character(
+    connection: &Connection,
+    account: u32,
+    password: &str,
+    name: &str,
+) -> rusqlite::Result<Option<(Entity, Position)>> {
+    if !authenticate(connection, account, password)? {
+        return Ok(None);
+    }
+    let Some((mut character, position)) = connection
+        .query_row(
+            "SELECT * FROM characters WHERE name = ?1 AND account = ?2",
+            params![name, account],
+            character,
+        )
+        .optional()?
+    else {
+        return Ok(None);
+    };
+    let Some(Name(name)) = character.name().cloned() else {
+        return Ok(None);
+    };
+
+    let mut statement = connection
+        .prepare("SELECT skill, level, tries FROM character_skills WHERE character = ?1")?;
+    let skills = statement
+        .query_map([&name], |row| {
+            Ok((
+                row.get::<_, u8>(0)?,
+                Skill {
+                    level: row.get(1)?,
+                    tries: row.get(2)?,
+                },
+            ))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?
+        .into_iter()
+        .filter_map(|(skill, value)| {
+            Some((
+                SkillType::ALL
+                    .into_iter()
+                    .find(|other| *other as u8 == skill)?,
+                value,
+            ))
+        })
+        .collect();
+
+    let mut statement =
+        connection.prepare("SELECT key, value FROM character_storage WHERE character = ?1")?;
+    let storage = statement
+        .query_map([&name], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<rusqlite::Result<_>>()?;
+
+    let mut statement = connection.prepare(&format!(
+        "SELECT {} FROM character_items WHERE character = ?1",
+        ITEM_COLUMNS
+    ))?;
+    let rows = statement
+        .query_map([&name], item_row)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    let inventory = nested(rows)
+        .into_iter()
+        .filter_map(|(slot, item)| {
+            let slot = InventorySlot::try_from(u8::try_from(slot).ok()?).ok()?;
+            Some((slot, restored(&item)))
+        })
+        .collect();
+
+    for attribute in [
+        Box::new(Skills(skills)) as Box<dyn Attribute>,
+        Box::new(Storage(storage)),
+        Box::new(Inventory(inventory)),
+    ] {
+        character
+            .attributes
+            .insert(attribute.as_name().to_string(), attribute);
+    }
+    Ok(Some((character, position)))
+}
+
+fn write_character(
+    transaction: &Transaction,
+    character: &Entity,
+    position: &Position,
+) -> rusqlite::Result<()> {
+    let (
+        Some(Name(name)),
+        Some(Account(account)),
+        Some(Direction(direction)),
+        Some(health),
+        Some(mana),
+        Some(Soul(soul)),
+        Some(Capacity(capacity)),
+        Some(Stamina(stamina)),
+        Some(Speed(speed)),
+        Some(Level(level)),
+        Some(Experience(experience)),
+        Some(magic_level),
+    ) = (
+        character.name(),
+        character.account(),
+        character.direction(),
+        character.health(),
+        character.mana(),
+        character.soul(),
+        character.capacity(),
+        character.stamina(),
+        character.speed(),
+        character.level(),
+        character.experience(),
+        character.magic_level(),
+    )
+    else {
+        println!(
+            "Could not save character {:?}, it misses attributes",
+            character.name()
+        );
+        return Ok(());
+    };
+    let outfit = character.outfit().cloned().unwrap_or(Outfit {
+        r#type: 128,
+        head: 0,
+        body: 0,
+        legs: 0,
+        feet: 0,
+        addons: 0,
+    });
+
+    transaction.execute(
+        "INSERT OR REPLACE INTO characters (
+            name, account, x, y, z, direction, health, max_health, mana, max_mana, soul, capacity, stamina,
+            level, experience, magic_level, mana_spent, speed,
+            outfit_type, outfit_head, outfit_body, outfit_legs, outfit_feet, outfit_addons
+        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22, ?23, ?24)",
+        params![
+            name, account, position.x, position.y, position.z, u8::from(*direction), health.value, health.max, mana.value, mana.max, soul, capacity, stamina,
+            level, experience, magic_level.level, magic_level.mana_spent, speed,
+            outfit.r#type, outfit.head, outfit.body, outfit.legs, outfit.feet, outfit.addons,
+        ],
+    )?;
+
+    for table in ["character_skills", "character_storage", "character_items"] {
+        transaction.execute(
+            &format!("DELETE FROM {} WHERE character = ?1", table),
+            [name],
+        )?;
+    }
+    if let Some(Skills(skills)) = character.skills() {
+        let mut statement = transaction.prepare(
+            "INSERT INTO character_skills (character, skill, level, tries) VALUES (?1, ?2, ?3, ?4)",
+        )?;
+        for (skill, Skill { level, tries }) in skills {
+            statement.execute(params![name, *skill as u8, level, tries])?;
+        }
+    }
+    if let Some(Storage(storage)) = character.storage() {
+        let mut statement = transaction
+            .prepare("INSERT INTO character_storage (character, key, value) VALUES (?1, ?2, ?3)")?;
+        for (key, value) in storage {
+            statement.execute(params![name, key, value])?;
+        }
+    }
+    if let Some(Inventory(inventory)) = character.inventory() {
+        let mut statement = transaction.prepare(&format!(
+            "INSERT INTO character_items (character, {}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            ITEM_COLUMNS
+        ))?;
+        let items: Vec<(usize, SavedItem)> = inventory
+            .iter()
+            .filter_map(|(slot, item)| Some((usize::from(u8::from(*slot)), as_saved(item)?)))
+            .collect();
+        for (index, parent, slot, item) in item_rows(items.iter().map(|(slot, item)| (*slot, item)))
+        {
+            statement.execute(params![
+                name,
+                index,
+                parent,
+                slot,
+                item.id,
+                item.count,
+                item.action_id,
+                item.decay,
+                item.contents.is_some()
+            ])?;
+        }
+    }
+    Ok(())
+}
+
+/// Items lying on the tile, creatures are saved on their own.
+fn kept_items<'a>(
+    world: &'a World,
+    position: &Position,
+) -> impl Iterator<Item = (EntityId, &'a Entity)> {
+    world
+        .entities(position)
+        .filter(|(_, entity)| entity.item().is_some())
+}
+
+fn saved_items(world: &World, position: &Position) -> Vec<SavedItem> {
+    kept_items(world, position)
+        .filter_map(|(_, entity)| as_saved(entity))
+        .collect()
+}
+
+fn write_tile(
+    transaction: &Transaction,
+    position: &Position,
+    items: &[SavedItem],
+) -> rusqlite::Result<()> {
+    transaction.execute(
+        "DELETE FROM tile_items WHERE x = ?1 AND y = ?2 AND z = ?3",
+        params![position.x, position.y, position.z],
+    )?;
+    let mut statement = transaction.prepare(&format!(
+        "INSERT INTO tile_items (x, y, z, {}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+        ITEM_COLUMNS
+    ))?;
+    for (index, parent, slot, item) in item_rows(items.iter().enumerate()) {
+        statement.execute(params![
+            position.x,
+            position.y,
+            position.z,
+            index,
+            parent,
+            slot,
+            item.id,
+            item.count,
+            item.action_id,
+            item.decay,
+            item.contents.is_some()
+        ])?;
+    }
+    Ok(())
+}
+
+fn read_world(connection: &Connection) -> rusqlite::Result<HashMap<Position, Vec<SavedItem>>> {
+    let mut statement =
+        connection.prepare(&format!("SELECT x, y, z, {} FROM tile_items", ITEM_COLUMNS))?;
+    let rows = statement.query_map([], |row| {
+        Ok((
+            Position(row.get("x")?, row.get("y")?, row.get("z")?),
+            item_row(row)?,
+        ))
+    })?;
+    let mut tiles: HashMap<Position, Vec<ItemRow<SavedItem>>> = HashMap::new();
+    for row in rows {
+        let (position, item) = row?;
+        tiles.entry(position).or_default().push(item);
+    }
+    Ok(tiles
+        .into_iter()
+        .map(|(position, rows)| {
+            (
+                position,
+                nested(rows).into_iter().map(|(_, item)| item).collect(),
+            )
+        })
+        .collect())
+}
+
+/// Brings the items of the tile back to the saved ones, those still in place keep their other attributes.
+fn restore_tile(world: &World, position: &Position, saved: &[SavedItem]) -> CommandsType {
+    let mut commands = Vec::new();
+    let mut added = Vec::new();
+    for pair in kept_items(world, position).zip_longest(saved) {
+        let (id, entity, saved) = match pair {
+            EitherOrBoth::Both((id, entity), saved) => (id, entity, saved),
+            EitherOrBoth::Left((id, _)) => {
+                commands.push(Box::new(RemoveEntityCommand { entity: id }) as CommandType);
+                continue;
+            }
+            EitherOrBoth::Right(saved) => {
+                added.push(restored(saved));
+                continue;
+            }
+        };
+        let item = restored(saved);
+        // an item changing layers is put back instead, as is a decaying one so that its timer starts
+        // when it is added
+        if map::stack_order(entity) != map::stack_order(&item)
+            || entity.decay().is_some()
+            || item.decay().is_some()
+        {
+            commands.push(Box::new(RemoveEntityCommand { entity: id }) as CommandType);
+            added.push(item);
+            continue;
+        }
+        if entity.item().map(|Item(id)| *id) != Some(saved.id) {
+            commands.push(Box::new(SetEntityAttributeCommand {
+                entity: id,
+                attribute: Box::new(Item(saved.id)),
+            }) as CommandType);
+        }
+        if as_saved(entity).and_then(|kept| kept.contents) != saved.contents {
+            commands.push(match item.container() {
+                Some(container) => Box::new(SetEntityAttributeCommand {
+                    entity: id,
+                    attribute: Box::new(container.clone()),
+                }) as CommandType,
+                None => Box::new(RemoveEntityAttributeCommand {
+                    entity: id,
+                    attribute: Box::new(Container(Vec::new())),
+                }) as CommandType,
+            });
+        }
+        match (entity.count(), &saved.count) {
+            (Some(Count(count)), Some(saved_count)) if count == saved_count => {}
+            (_, Some(saved_count)) => commands.push(Box::new(SetEntityAttributeCommand {
+                entity: id,
+                attribute: Box::new(Count(*saved_count)),
+            }) as CommandType),
+            (Some(count), None) => commands.push(Box::new(RemoveEntityAttributeCommand {
+                entity: id,
+                attribute: Box::new(count.clone()),
+            }) as CommandType),
+            (None, None) => {}
+        }
+    }
+
+    // piles are built from their lowest item up, the other layers keep the saved order
+    let (piled, others): (Vec<Entity>, Vec<Entity>) = added
+        .into_iter()
+        .partition(|entity| map::stack_order(entity) == StackOrder::Bottom);
+    for entity in others.into_iter().chain(piled.into_iter().rev()) {
+        commands.push(Box::new(AddEntityCommand {
+            position: position.clone(),
+            entity,
+        }) as CommandType);
+    }
+    commands
+}
+
+/// The database along with the items of the tiles as they were last written there.
+struct Store {
+    connection: Connection,
+    tiles: HashMap<Position, Vec<SavedItem>>,
+}
+
+type Job = Box<dyn FnOnce(&mut Store) + Send>;
+
+/// Queue of the jobs run on the database, one after the other in the order they were queued.
+#[attribute]
+pub struct Database(UnboundedSender<Job>);
+
+impl Database {
+    fn queue(&self, job: Job) {
+        if self.0.send(job).is_err() {
+            println!("Could not queue a job for {}", DATABASE_PATH);
+        }
+    }
+
+    /// Queues the job right away, its result is there once it has been run.
+    fn run<T: Send + 'static>(
+        &self,
+        job: impl FnOnce(&mut Store) -> T + Send + 'static,
+    ) -> oneshot::Receiver<T> {
+        let (sender, receiver) = oneshot::channel();
+        self.queue(Box::new(move |store| {
+            let _ = sender.send(job(store));
+        }));
+        receiver
+    }
+}
+
+// the blocking database calls are kept off the game loop, the store is handed to each of them in turn
+fn work(mut store: Store, mut jobs: UnboundedReceiver<Job>) -> TaskType {
+    Box::pin(stream! {
+        while let Some(job) = jobs.recv().await {
+            store = match task::spawn_blocking(move || {
+                job(&mut store);
+                store
+            })
+            .await
+            {
+                Ok(store) => store,
+                Err(error) => {
+                    println!("Could not run a job on {}: {}", DATABASE_PATH, error);
+                    break;
+                }
+            };
+        }
+        yield None;
+    })
+}
+
+/// Writes the characters and the tiles that changed since they were last written.
+fn save(
+    store: &mut Store,
+    characters: &[(Entity, Position)],
+    tiles: Option<HashMap<Position, Vec<SavedItem>>>,
+) -> rusqlite::Result<()> {
+    let changed: Vec<(Position, Vec<SavedItem>)> = tiles
+        .into_iter()
+        .flatten()
+        .filter(|(position, items)| {
+            store
+                .tiles
+                .get(position)
+                .map_or(!items.is_empty(), |saved| saved != items)
+        })
+        .collect();
+    let transaction = store.connection.transaction()?;
+    for (character, position) in characters {
+        write_character(&transaction, character, position)?;
+    }
+    for (position, items) in &changed {
+        write_tile(&transaction, position, items)?;
+    }
+    transaction.commit()?;
+    store.tiles.extend(changed);
+    Ok(())
+}
+
+fn queue_save(
+    database: &Database,
+    characters: Vec<(Entity, Position)>,
+    tiles: Option<HashMap<Position, Vec<SavedItem>>>,
+) {
+    database.queue(Box::new(move |store| {
+        if let Err(error) = save(store, &characters, tiles) {
+            println!("Could not save {}: {}", DATABASE_PATH, error);
+        }
+    }));
+}
+
+/// Queues the character to be written, whatever is queued afterwards finds it saved.
+pub fn save_character(database: &Database, character: Entity, position: Position) {
+    queue_save(database, vec![(character, position)], None);
+}
+
+/// Names of the characters on the account, none when there is no such account or the password is wrong.
+pub fn characters(
+    database: &Database,
+    account: u32,
+    password: String,
+) -> impl Future<Output = Option<Vec<String>>> {
+    let characters = database.run(move |store| {
+        read_characters(&store.connection, account, &password).unwrap_or_else(|error| {
+            println!(
+                "Could not load characters of account {}: {}",
+                account, error
+            );
+            None
+        })
+    });
+    async move { characters.await.ok().flatten() }
+}
+
+/// The character as it was last saved and where it stood, if it belongs to the account.
+pub fn load_character(
+    database: &Database,
+    account: u32,
+    password: String,
+    name: String,
+) -> impl Future<Output = Option<(Entity, Position)>> {
+    let character = database.run(move |store| {
+        read_character(&store.connection, account, &password, &name).unwrap_or_else(|error| {
+            println!("Could not load character {}: {}", name, error);
+            None
+        })
+    });
+    async move { character.await.ok().flatten() }
+}
+
+system! {
+    #[event]
+    pub struct SaveEvent;
+
+    task! {
+        let mut interval = time::interval(SAVE_INTERVAL);
+        // the first tick completes right away, there is nothing new to save yet
+        interval.tick().await;
+
+        loop {
+            interval.tick().await;
+            yield Some(Arc::new(SaveEvent) as EventType);
+        }
+    }
+
+    // the map has to be loaded before, saved items are laid over it
+    #[effect(SystemsLoadedEvent)]
+    fn load_world(_: EventType, _: GameAttributesType, world: WorldType) -> EffectResultType {
+        let world = world.lock().unwrap();
+        let loaded = open().and_then(|mut connection| {
+            create_seed_account(&mut connection)?;
+            let (names, tiles) = (read_names(&connection)?, read_world(&connection)?);
+            Ok((names, Store { connection, tiles }))
+        });
+        let (names, store) = match loaded {
+            Ok(loaded) => loaded,
+            Err(error) => {
+                println!("Could not load {}: {}", DATABASE_PATH, error);
+                return Some((Vec::new(), Vec::new()));
+            }
+        };
+        let (sender, jobs) = mpsc::unbounded_channel();
+        let commands = store
+            .tiles
+            .iter()
+            .filter(|(position, _)| world.tile(position).is_some())
+            .flat_map(|(position, saved)| restore_tile(&world, position, saved))
+            .chain([
+                Box::new(SetGameAttributeCommand(Box::new(CharacterNames(names)))) as CommandType,
+                Box::new(SetGameAttributeCommand(Box::new(Database(sender)))) as CommandType,
+            ])
+            .collect();
+        Some((commands, vec![work(store, jobs)]))
+    }
+
+    // only a copy is taken here, the database is written outside of the game loop
+    #[effect(SaveEvent)]
+    fn handle_save(_: EventType, attributes: GameAttributesType, world: WorldType) -> EffectResultType {
+        let game_attributes = attributes.lock().unwrap();
+        let world = world.lock().unwrap();
+        if let Some(database) = game_attributes.database() {
+            let players = world
+                .creatures()
+                .filter_map(|(_, id)| Some((world.entity(id).filter(|entity| entity.player().is_some())?.clone(), world.position(id)?)))
+                .collect();
+            let tiles = world.tiles().map(|(position, _)| (position.clone(), saved_items(&world, position))).collect();
+            queue_save(database, players, Some(tiles));
+        }
+        Some((Vec::new(), Vec::new()))
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+
+    fn saved(id: u16) -> SavedItem {
+        SavedItem {
+            id,
+            count: None,
+            action_id: None,
+            decay: None,
+            contents: None,
+        }
+    }
+
+    fn add_account(store: &Store, account: u32, password: &str) {
+        store
+            .connection
+            .execute(
+                "INSERT INTO accounts (id, password_hash) VALUES (?1, ?2)",
+                params![account, hash(password).unwrap()],
+            )
+            .unwrap();
+    }
+
+    fn store() -> Store {
+        let connection = Connection::open_in_memory().unwrap();
+        connection.execute_batch(SCHEMA).unwrap();
+        Store {
+            connection,
+            tiles: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn hashed_password_verifies_only_itself() {
+        let hashed = hash("secret").unwrap();
+        assert_ne!(hashed, "secret");
+        assert!(verify("secret", &hashed));
+        assert!(!verify("Secret", &hashed));
+        assert!(!verify("secret", "secret"));
+    }
+
+    #[test]
+    fn same_password_gets_another_salt() {
+        assert_ne!(hash("secret").unwrap(), hash("secret").unwrap());
+    }
+
+    #[test]
+    fn only_changed_tiles_are_written() {
+        let mut store = store();
+        let (first, second) = (Position(1, 1, 7), Position(2, 1, 7));
+        let tiles = |first_item| {
+            HashMap::from([
+                (first.clone(), vec![saved(first_item)]),
+                (
+                    second.clone(),
+                    vec![SavedItem {
+                        count: Some(3),
+                        decay: Some(1500),
+                        ..saved(200)
+                    }],
+                ),
+            ])
+        };
+
+        save(&mut store, &[], Some(tiles(100))).unwrap();
+        assert_eq!(read_world(&store.connection).unwrap(), tiles(100));
+
+        // a tile written again would get its rows back
+        store
+            .connection
+            .execute("DELETE FROM tile_items WHERE x = 2", [])
+            .unwrap();
+        save(&mut store, &[], Some(tiles(101))).unwrap();
+        assert_eq!(
+            read_world(&store.connection).unwrap(),
+            HashMap::from([(first.clone(), vec![saved(101)])])
+        );
+    }
+
+    #[test]
+    fn decaying_items_come_back_with_their_time_left() {
+        let mut world = World::new().ordered_by(map::stack_order);
+        let position = Position(1, 1, 7);
+        world.add_tile(position.clone(), vec![entity![Item(Items::Grass.into())]]);
+        let saved = [
+            saved(Items::Grass.into()),
+            SavedItem {
+                decay: Some(60_000),
+                ..saved(Items::DeadRat.into())
+            },
+        ];
+
+        let added: Vec<Entity> = restore_tile(&world, &position, &saved)
+            .into_iter()
+            .filter_map(|command| {
+                Some(
+                    command
+                        .as_any()
+                        .downcast_ref::<AddEntityCommand>()?
+                        .entity
+                        .clone(),
+                )
+            })
+            .collect();
+        assert_eq!(added.len(), 1);
+        let remaining = added[0].decay().unwrap().remaining();
+        assert!(remaining > Duration::from_secs(59) && remaining <= Duration::from_secs(60));
+    }
+
+    #[test]
+    fn logged_out_character_loads_as_it_left() {
+        let mut store = store();
+        add_account(&store, 1, "secret");
+        let (sender, mut jobs) = mpsc::unbounded_channel();
+        let database = Database(sender);
+        let mut character = new_character("Tester", 1);
+        let soul = Soul(5);
+        character
+            .attributes
+            .insert(soul.as_name().to_string(), Box::new(soul));
+
+        save_character(&database, character, Position(100, 100, 7));
+        let loaded = load_character(&database, 1, "secret".to_string(), "tester".to_string());
+        while let Ok(job) = jobs.try_recv() {
+            job(&mut store);
+        }
+        let (character, position) = block_on(loaded).unwrap();
+        assert_eq!(position, Position(100, 100, 7));
+        assert_eq!(
+            character.name().map(|Name(name)| name.as_str()),
+            Some("Tester")
+        );
+        assert_eq!(character.soul().map(|Soul(soul)| *soul), Some(5));
+        assert_eq!(
+            character.inventory().map(|Inventory(items)| items.len()),
+            Some(4)
+        );
+    }
+
+    #[test]
+    fn filled_backpack_comes_back_with_its_contents() {
+        let mut store = store();
+        add_account(&store, 1, "secret");
+        let backpack = SavedItem {
+            contents: Some(vec![
+                SavedItem {
+                    count: Some(10),
+                    ..saved(Items::PlatinumCoin.into())
+                },
+                SavedItem {
+                    contents: Some(vec![SavedItem {
+                        action_id: Some(1001),
+                        ..saved(Items::Key.into())
+                    }]),
+                    ..saved(Items::Backpack.into())
+                },
+                SavedItem {
+                    contents: Some(Vec::new()),
+                    ..saved(Items::Backpack.into())
+                },
+            ]),
+            ..saved(Items::Backpack.into())
+        };
+        let mut character = new_character("Tester", 1);
+        let inventory = Inventory(HashMap::from([
+            (InventorySlot::Backpack, restored(&backpack)),
+            (InventorySlot::Left, item(Items::Bow.into(), None)),
+        ]));
+        character
+            .attributes
+            .insert(inventory.as_name().to_string(), Box::new(inventory));
+
+        save(&mut store, &[(character, Position(100, 100, 7))], None).unwrap();
+        let (character, _) = read_character(&store.connection, 1, "secret", "Tester")
+            .unwrap()
+            .unwrap();
+        let Some(Inventory(items)) = character.inventory() else {
+            panic!("the character has an inventory");
+        };
+        assert_eq!(items.len(), 2);
+        assert_eq!(
+            items.get(&InventorySlot::Backpack).and_then(as_saved),
+            Some(backpack)
+        );
+    }
+
+    #[test]
+    fn corpse_loot_comes_back_with_the_tile() {
+        let mut store = store();
+        let position = Position(1, 1, 7);
+        let corpse = SavedItem {
+            decay: Some(60_000),
+            contents: Some(vec![
+                SavedItem {
+                    count: Some(30),
+                    ..saved(Items::GoldCoin.into())
+                },
+                saved(Items::Sword.into()),
+            ]),
+            ..saved(Items::DeadTroll.into())
+        };
+        let tiles = HashMap::from([(
+            position.clone(),
+            vec![saved(Items::Grass.into()), corpse.clone()],
+        )]);
+
+        save(&mut store, &[], Some(tiles.clone())).unwrap();
+        assert_eq!(read_world(&store.connection).unwrap(), tiles);
+
+        let mut world = World::new().ordered_by(map::stack_order);
+        world.add_tile(position.clone(), vec![entity![Item(Items::Grass.into())]]);
+        let added: Vec<Entity> = restore_tile(&world, &position, &tiles[&position])
+            .into_iter()
+            .filter_map(|command| {
+                Some(
+                    command
+                        .as_any()
+                        .downcast_ref::<AddEntityCommand>()?
+                        .entity
+                        .clone(),
+                )
+            })
+            .collect();
+        assert_eq!(added.len(), 1);
+        assert_eq!(
+            as_saved(&added[0]).and_then(|added| added.contents),
+            corpse.contents
+        );
+    }
+}
@@ -1,4 +1,4 @@
 pub use super::Actions;
 pub use super::Items;
-pub use super::{attributes::*, definitions::*, events::*};
+pub use super::{attributes::*, commands::*, definitions::*, events::*};
 pub use crate::network::attributes::*;
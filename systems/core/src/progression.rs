@@ -0,0 +1,130 @@
+use crate::prelude::*;
+use skyless_core::prelude::*;
+
+const HEALTH_PER_LEVEL: u16 = 5;
+const MANA_PER_LEVEL: u16 = 5;
+const CAPACITY_PER_LEVEL: u32 = 1000;
+
+fn advance(player: &CreatureId, text: String) -> CommandType {
+    Box::new(SendTextCommand {
+        player: player.clone(),
+        class: MessageClasses::EventAdvance,
+        text,
+    }) as CommandType
+}
+
+system! {
+    #[handler(AddSkillTriesCommand)]
+    fn handle_add_skill_tries(command: CommandType, _: GameAttributesType, world: WorldType) -> EffectResultType {
+        let AddSkillTriesCommand { player, skill, tries } = command.as_any().downcast_ref::<AddSkillTriesCommand>().unwrap();
+        let mut commands = Vec::new();
+        let world = world.lock().unwrap();
+
+        if let Some(mut skill_commands) = (|| {
+            let id = world.find_creature(player)?;
+            let mut skills = world.entity(id)?.skills()?.clone();
+            let mut current = skills.get(*skill);
+            let mut commands = Vec::new();
+            current.tries += tries;
+            while current.tries >= Skill::tries_for(*skill, current.level + 1) {
+                current.tries -= Skill::tries_for(*skill, current.level + 1);
+                current.level += 1;
+                commands.push(advance(player, format!("You advanced in {}.", skill.name())));
+            }
+            skills.0.insert(*skill, current);
+            commands.insert(0, Box::new(SetEntityAttributeCommand {
+                entity: id,
+                attribute: Box::new(skills),
+            }) as CommandType);
+            Some(commands)
+        })() {
+            commands.append(&mut skill_commands);
+        }
+
+        Some((commands, Vec::new()))
+    }
+
+    #[handler(AddExperienceCommand)]
+    fn handle_add_experience(command: CommandType, _: GameAttributesType, world: WorldType) -> EffectResultType {
+        let AddExperienceCommand { player, experience } = command.as_any().downcast_ref::<AddExperienceCommand>().unwrap();
+        let mut commands = Vec::new();
+        let world = world.lock().unwrap();
+
+        if let Some(mut experience_commands) = (|| {
+            let id = world.find_creature(player)?;
+            let entity = world.entity(id)?;
+            let Level(level) = entity.level()?;
+            let Experience(current) = entity.experience()?;
+            let total = current.saturating_add(*experience);
+            let mut new_level = *level;
+            while new_level < u16::MAX && total >= Level::experience_for(new_level + 1) {
+                new_level += 1;
+            }
+            let mut commands = vec![Box::new(SetEntityAttributeCommand {
+                entity: id,
+                attribute: Box::new(Experience(total)),
+            }) as CommandType];
+            if new_level > *level {
+                let levels = new_level - level;
+                if let Some(Health { value, max }) = entity.health() {
+                    let gained = levels.saturating_mul(HEALTH_PER_LEVEL);
+                    commands.push(Box::new(SetEntityAttributeCommand {
+                        entity: id,
+                        attribute: Box::new(Health { value: value.saturating_add(gained), max: max.saturating_add(gained) }),
+                    }) as CommandType);
+                }
+                if let Some(Mana { value, max }) = entity.mana() {
+                    let gained = levels.saturating_mul(MANA_PER_LEVEL);
+                    commands.push(Box::new(SetEntityAttributeCommand {
+                        entity: id,
+                        attribute: Box::new(Mana { value: value.saturating_add(gained), max: max.saturating_add(gained) }),
+                    }) as CommandType);
+                }
+                if let Some(Capacity(capacity)) = entity.capacity() {
+                    commands.push(Box::new(SetEntityAttributeCommand {
+                        entity: id,
+                        attribute: Box::new(Capacity(capacity.saturating_add(u32::from(levels) * CAPACITY_PER_LEVEL))),
+                    }) as CommandType);
+                }
+                commands.push(Box::new(SetEntityAttributeCommand {
+                    entity: id,
+                    attribute: Box::new(Level(new_level)),
+                }) as CommandType);
+                commands.push(advance(player, format!("You advanced from Level {} to Level {}.", level, new_level)));
+            }
+            Some(commands)
+        })() {
+            commands.append(&mut experience_commands);
+        }
+
+        Some((commands, Vec::new()))
+    }
+
+    #[handler(AddManaSpentCommand)]
+    fn handle_add_mana_spent(command: CommandType, _: GameAttributesType, world: WorldType) -> EffectResultType {
+        let AddManaSpentCommand { player, mana } = command.as_any().downcast_ref::<AddManaSpentCommand>().unwrap();
+        let mut commands = Vec::new();
+        let world = world.lock().unwrap();
+
+        if let Some(mut magic_commands) = (|| {
+            let id = world.find_creature(player)?;
+            let MagicLevel { mut level, mana_spent } = world.entity(id)?.magic_level()?.clone();
+            let mut mana_spent = mana_spent + mana;
+            let mut commands = Vec::new();
+            while mana_spent >= MagicLevel::mana_for(level + 1) {
+                mana_spent -= MagicLevel::mana_for(level + 1);
+                level += 1;
+                commands.push(advance(player, format!("You advanced to magic level {}.", level)));
+            }
+            commands.insert(0, Box::new(SetEntityAttributeCommand {
+                entity: id,
+                attribute: Box::new(MagicLevel { level, mana_spent }),
+            }) as CommandType);
+            Some(commands)
+        })() {
+            commands.append(&mut magic_commands);
+        }
+
+        Some((commands, Vec::new()))
+    }
+}
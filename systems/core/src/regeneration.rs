@@ -0,0 +1,103 @@
+use crate::prelude::*;
+use skyless_core::{prelude::*, World};
+use std::time::Duration;
+use tokio::time;
+
+const HEALTH_GAIN: u16 = 1;
+const MANA_GAIN: u16 = 2;
+const SOUL_GAIN: u8 = 1;
+
+fn players(world: &World) -> Vec<(EntityId, &Entity)> {
+    world
+        .creatures()
+        .filter_map(|(_, id)| Some((id, world.entity(id)?)))
+        .filter(|(_, entity)| entity.player().is_some())
+        .collect()
+}
+
+fn set(id: EntityId, attribute: impl Attribute + 'static) -> CommandType {
+    Box::new(SetEntityAttributeCommand {
+        entity: id,
+        attribute: Box::new(attribute),
+    }) as CommandType
+}
+
+system! {
+    #[event]
+    pub struct RegenerationEvent;
+
+    #[event]
+    pub struct SoulRegenerationEvent;
+
+    #[event]
+    pub struct StaminaEvent;
+
+    task! {
+        let mut interval = time::interval(Duration::from_secs(6));
+
+        loop {
+            interval.tick().await;
+            yield Some(Arc::new(RegenerationEvent) as EventType);
+        }
+    }
+
+    task! {
+        let mut interval = time::interval(Duration::from_secs(120));
+
+        loop {
+            interval.tick().await;
+            yield Some(Arc::new(SoulRegenerationEvent) as EventType);
+        }
+    }
+
+    // stamina is only drained while the player is online, so one minute of play costs one minute
+    task! {
+        let mut interval = time::interval(Duration::from_secs(60));
+
+        loop {
+            interval.tick().await;
+            yield Some(Arc::new(StaminaEvent) as EventType);
+        }
+    }
+
+    #[effect(RegenerationEvent)]
+    fn handle_regeneration(_: EventType, _: GameAttributesType, world: WorldType) -> EffectResultType {
+        let world = world.lock().unwrap();
+        let mut commands = Vec::new();
+        for (id, entity) in players(&world) {
+            if let Some(Health { value, max }) = entity.health().filter(|health| health.value > 0 && health.value < health.max) {
+                commands.push(set(id, Health { value: (value + HEALTH_GAIN).min(*max), max: *max }));
+            }
+            if let Some(Mana { value, max }) = entity.mana().filter(|mana| mana.value < mana.max) {
+                commands.push(set(id, Mana { value: (value + MANA_GAIN).min(*max), max: *max }));
+            }
+        }
+        Some((commands, Vec::new()))
+    }
+
+    #[effect(SoulRegenerationEvent)]
+    fn handle_soul_regeneration(_: EventType, _: GameAttributesType, world: WorldType) -> EffectResultType {
+        let world = world.lock().unwrap();
+        let commands = players(&world)
+            .into_iter()
+            .filter_map(|(id, entity)| {
+                let Soul(soul) = entity.soul().filter(|Soul(soul)| *soul < Soul::MAX)?;
+                Some(set(id, Soul((soul + SOUL_GAIN).min(Soul::MAX))))
+            })
+            .collect();
+        Some((commands, Vec::new()))
+    }
+
+    #[effect(StaminaEvent)]
+    fn handle_stamina(_: EventType, _: GameAttributesType, world: WorldType) -> EffectResultType {
+        let world = world.lock().unwrap();
+        let commands = players(&world)
+            .into_iter()
+            .filter_map(|(id, entity)| {
+                let Stamina(stamina) = entity.stamina().filter(|Stamina(stamina)| *stamina > 0)?;
+                Some(set(id, Stamina(stamina - 1)))
+            })
+            .collect();
+        Some((commands, Vec::new()))
+    }
+}
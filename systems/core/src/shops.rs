@@ -0,0 +1,208 @@
+use crate::{
+    npcs::{says, DialogueEvent},
+    prelude::*,
+};
+use skyless_core::{prelude::*, World};
+
+const NOT_ENOUGH_MONEY: &str = "You do not have enough money.";
+const NOT_ENOUGH_CAPACITY: &str = "You do not have enough capacity.";
+const NOT_ENOUGH_ROOM: &str = "You do not have enough room.";
+const NOT_ENOUGH_ITEMS: &str = "You do not have this object.";
+
+// from the most to the least worth, change is given with as few coins as possible
+const COINS: [Items; 3] = [Items::CrystalCoin, Items::PlatinumCoin, Items::GoldCoin];
+
+/// Gold the carried coins are worth.
+pub fn money(inventory: &Inventory) -> u64 {
+    COINS
+        .iter()
+        .filter_map(|coin| Some(u64::from(inventory.count(*coin)) * coin.value()?))
+        .sum()
+}
+
+/// Puts coins worth `amount` gold into the inventory, nothing changes when there is no room for them.
+pub fn give(inventory: &mut Inventory, amount: u64) -> bool {
+    let mut given = inventory.clone();
+    let mut left = amount;
+    for coin in COINS {
+        let value = coin.value().unwrap();
+        let count = left / value;
+        left %= value;
+        if count > 0 && !given.add(coin, count as u32) {
+            return false;
+        }
+    }
+    *inventory = given;
+    true
+}
+
+/// Takes coins worth `amount` gold from the inventory, bigger coins are changed when needed.
+pub fn pay(inventory: &mut Inventory, amount: u64) -> bool {
+    let total = money(inventory);
+    if total < amount {
+        return false;
+    }
+    let mut paid = inventory.clone();
+    for coin in COINS {
+        paid.remove(coin, paid.count(coin));
+    }
+    if !give(&mut paid, total - amount) {
+        return false;
+    }
+    *inventory = paid;
+    true
+}
+
+/// What the NPC the player trades with offers for the item.
+fn offer<'a>(
+    world: &World,
+    npc_types: &'a NpcTypes,
+    entity: &Entity,
+    item: u16,
+) -> Option<&'a ShopOffer> {
+    let Shop(npc) = entity.shop()?;
+    let Npc(name) = world.entity(world.find_creature(npc)?)?.npc()?;
+    npc_types
+        .0
+        .get(name)?
+        .shop
+        .iter()
+        .find(|offer| offer.item == item)
+}
+
+/// Whether the player can carry the inventory, coins handed out as change included.
+fn carries(entity: &Entity, inventory: &Inventory) -> bool {
+    entity
+        .capacity()
+        .is_some_and(|Capacity(capacity)| inventory.weight() <= *capacity)
+}
+
+fn trade(player: &CreatureId, id: EntityId, inventory: Inventory, text: String) -> CommandsType {
+    vec![
+        Box::new(SetEntityAttributeCommand {
+            entity: id,
+            attribute: Box::new(inventory),
+        }) as CommandType,
+        Box::new(SendTextCommand {
+            player: player.clone(),
+            class: MessageClasses::InfoDescription,
+            text,
+        }) as CommandType,
+    ]
+}
+
+system! {
+    #[effect(DialogueEvent)]
+    fn handle_trade_request(event: EventType, attributes: GameAttributesType, world: WorldType) -> EffectResultType {
+        let DialogueEvent { npc, player, text } = event.as_any().downcast_ref::<DialogueEvent>().unwrap();
+        let game_attributes = attributes.lock().unwrap();
+        let world = world.lock().unwrap();
+        let mut commands = Vec::new();
+
+        if let Some(command) = (|| {
+            if !says(text, "trade") {
+                return None;
+            }
+            let Npc(name) = world.entity(world.find_creature(npc)?)?.npc()?;
+            let npc_type = game_attributes.npc_types()?.0.get(name)?;
+            if npc_type.shop.is_empty() {
+                return None;
+            }
+            Some(Box::new(SetEntityAttributeCommand {
+                entity: world.find_creature(player)?,
+                attribute: Box::new(Shop(npc.clone())),
+            }) as CommandType)
+        })() {
+            commands.push(command);
+        }
+
+        Some((commands, Vec::new()))
+    }
+
+    // the trade window is closed together with the conversation
+    #[effect(RemovedEntityEvent)]
+    fn handle_focus_lost(event: EventType, _: GameAttributesType, world: WorldType) -> EffectResultType {
+        let RemovedEntityEvent { entity, attribute_name } = event.as_any().downcast_ref::<RemovedEntityEvent>().unwrap();
+        let mut commands = Vec::new();
+        if attribute_name.as_str() != "focus" {
+            return Some((commands, Vec::new()));
+        }
+        let world = world.lock().unwrap();
+        let Some(npc) = world.entity(*entity).and_then(Entity::creature_id) else {
+            return Some((commands, Vec::new()));
+        };
+        for (_, player) in world.creatures() {
+            if let Some(shop) = world.entity(player).and_then(Entity::shop).filter(|Shop(shop_npc)| shop_npc == npc) {
+                commands.push(Box::new(RemoveEntityAttributeCommand {
+                    entity: player,
+                    attribute: Box::new(shop.clone()),
+                }) as CommandType);
+            }
+        }
+        Some((commands, Vec::new()))
+    }
+
+    #[effect(BuyEvent)]
+    fn handle_buy(event: EventType, attributes: GameAttributesType, world: WorldType) -> EffectResultType {
+        let BuyEvent { player, item, amount } = event.as_any().downcast_ref::<BuyEvent>().unwrap();
+        let game_attributes = attributes.lock().unwrap();
+        let world = world.lock().unwrap();
+
+        let commands = (|| {
+            let id = world.find_creature(player)?;
+            let entity = world.entity(id)?;
+            let offer = offer(&world, game_attributes.npc_types()?, entity, *item).filter(|offer| offer.buy > 0)?;
+            let bought = Items::try_from(*item).ok()?;
+            let cancel = |message| Some(vec![Box::new(SendTextCommand::cancel(player.clone(), message)) as CommandType]);
+
+            let amount = u32::from(*amount).max(1);
+            let price = u64::from(offer.buy) * u64::from(amount);
+            let mut inventory = entity.inventory().cloned().unwrap_or_else(|| Inventory(HashMap::new()));
+            if !pay(&mut inventory, price) {
+                return cancel(NOT_ENOUGH_MONEY);
+            }
+            if !inventory.add(bought, amount) {
+                return cancel(NOT_ENOUGH_ROOM);
+            }
+            if !carries(entity, &inventory) {
+                return cancel(NOT_ENOUGH_CAPACITY);
+            }
+            Some(trade(player, id, inventory, format!("Bought {}x {} for {} gold.", amount, offer.name, price)))
+        })()
+        .unwrap_or_default();
+
+        Some((commands, Vec::new()))
+    }
+
+    #[effect(SellEvent)]
+    fn handle_sell(event: EventType, attributes: GameAttributesType, world: WorldType) -> EffectResultType {
+        let SellEvent { player, item, amount } = event.as_any().downcast_ref::<SellEvent>().unwrap();
+        let game_attributes = attributes.lock().unwrap();
+        let world = world.lock().unwrap();
+
+        let commands = (|| {
+            let id = world.find_creature(player)?;
+            let entity = world.entity(id)?;
+            let offer = offer(&world, game_attributes.npc_types()?, entity, *item).filter(|offer| offer.sell > 0)?;
+            let sold = Items::try_from(*item).ok()?;
+            let cancel = |message| Some(vec![Box::new(SendTextCommand::cancel(player.clone(), message)) as CommandType]);
+
+            let amount = u32::from(*amount).max(1);
+            let price = u64::from(offer.sell) * u64::from(amount);
+            let mut inventory = entity.inventory().cloned().unwrap_or_else(|| Inventory(HashMap::new()));
+            if !inventory.remove(sold, amount) {
+                return cancel(NOT_ENOUGH_ITEMS);
+            }
+            if !give(&mut inventory, price) {
+                return cancel(NOT_ENOUGH_ROOM);
+            }
+            if !carries(entity, &inventory) {
+                return cancel(NOT_ENOUGH_CAPACITY);
+            }
+            Some(trade(player, id, inventory, format!("Sold {}x {} for {} gold.", amount, offer.name, price)))
+        })()
+        .unwrap_or_default();
+
+        Some((commands, Vec::new()))
+    }
+}
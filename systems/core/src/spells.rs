@@ -0,0 +1,483 @@
+use crate::{
+    combat,
+    geometry::{self, Areas},
+    prelude::*,
+};
+use skyless_core::{prelude::*, World};
+use std::time::{Duration, Instant};
+
+const RUNE_RANGE: u16 = 7;
+
+const EXHAUSTED: &str = "You are exhausted.";
+const NOT_ENOUGH_LEVEL: &str = "You do not have enough level.";
+const NOT_ENOUGH_MAGIC_LEVEL: &str = "You do not have enough magic level.";
+const NOT_ENOUGH_MANA: &str = "You do not have enough mana.";
+const NOT_ENOUGH_SOUL: &str = "You do not have enough soul.";
+const ONLY_ON_CREATURES: &str = "You can only use this rune on creatures.";
+
+fn default_spells() -> Vec<Spell> {
+    vec![
+        Spell {
+            name: "Light".into(),
+            trigger: SpellTriggers::Words("utevo lux".into()),
+            level: 8,
+            magic_level: 0,
+            mana: 20,
+            soul: 0,
+            cooldown: Duration::from_secs(1),
+            area: SpellAreas::Creature,
+            effect: MagicEffects::MagicBlue,
+            shoot: None,
+            action: SpellActions::Condition {
+                condition: ConditionType::Light,
+                duration: Duration::from_secs(6 * 60),
+                damage: 0,
+            },
+        },
+        Spell {
+            name: "Light Healing".into(),
+            trigger: SpellTriggers::Words("exura".into()),
+            level: 9,
+            magic_level: 1,
+            mana: 20,
+            soul: 0,
+            cooldown: Duration::from_secs(1),
+            area: SpellAreas::Creature,
+            effect: MagicEffects::MagicBlue,
+            shoot: None,
+            action: SpellActions::Heal { min: 20, max: 40 },
+        },
+        Spell {
+            name: "Intense Healing".into(),
+            trigger: SpellTriggers::Words("exura gran".into()),
+            level: 11,
+            magic_level: 2,
+            mana: 70,
+            soul: 0,
+            cooldown: Duration::from_secs(1),
+            area: SpellAreas::Creature,
+            effect: MagicEffects::MagicBlue,
+            shoot: None,
+            action: SpellActions::Heal { min: 40, max: 80 },
+        },
+        Spell {
+            name: "Haste".into(),
+            trigger: SpellTriggers::Words("utani hur".into()),
+            level: 14,
+            magic_level: 0,
+            mana: 60,
+            soul: 0,
+            cooldown: Duration::from_secs(2),
+            area: SpellAreas::Creature,
+            effect: MagicEffects::MagicGreen,
+            shoot: None,
+            action: SpellActions::Condition {
+                condition: ConditionType::Haste,
+                duration: Duration::from_secs(33),
+                damage: 0,
+            },
+        },
+        Spell {
+            name: "Magic Shield".into(),
+            trigger: SpellTriggers::Words("utamo vita".into()),
+            level: 14,
+            magic_level: 4,
+            mana: 50,
+            soul: 0,
+            cooldown: Duration::from_secs(2),
+            area: SpellAreas::Creature,
+            effect: MagicEffects::MagicBlue,
+            shoot: None,
+            action: SpellActions::Condition {
+                condition: ConditionType::ManaShield,
+                duration: Duration::from_secs(200),
+                damage: 0,
+            },
+        },
+        Spell {
+            name: "Fire Wave".into(),
+            trigger: SpellTriggers::Words("exevo flam hur".into()),
+            level: 18,
+            magic_level: 0,
+            mana: 25,
+            soul: 0,
+            cooldown: Duration::from_secs(2),
+            area: SpellAreas::Area(Areas::Wave(5)),
+            effect: MagicEffects::FireArea,
+            shoot: None,
+            action: SpellActions::Damage {
+                min: 30,
+                max: 50,
+                effect: MagicEffects::HitByFire,
+                color: TextColors::Orange,
+            },
+        },
+        Spell {
+            name: "Energy Beam".into(),
+            trigger: SpellTriggers::Words("exevo vis lux".into()),
+            level: 23,
+            magic_level: 0,
+            mana: 40,
+            soul: 0,
+            cooldown: Duration::from_secs(2),
+            area: SpellAreas::Area(Areas::Beam(5)),
+            effect: MagicEffects::EnergyHit,
+            shoot: None,
+            action: SpellActions::Damage {
+                min: 40,
+                max: 80,
+                effect: MagicEffects::EnergyHit,
+                color: TextColors::Purple,
+            },
+        },
+        Spell {
+            name: "Ultimate Healing Rune".into(),
+            trigger: SpellTriggers::Rune(Items::UltimateHealingRune),
+            level: 24,
+            magic_level: 4,
+            mana: 0,
+            soul: 0,
+            cooldown: Duration::from_secs(1),
+            area: SpellAreas::Creature,
+            effect: MagicEffects::MagicBlue,
+            shoot: None,
+            action: SpellActions::Heal { min: 250, max: 350 },
+        },
+        Spell {
+            name: "Great Fireball Rune".into(),
+            trigger: SpellTriggers::Rune(Items::GreatFireballRune),
+            level: 30,
+            magic_level: 4,
+            mana: 0,
+            soul: 0,
+            cooldown: Duration::from_secs(2),
+            area: SpellAreas::Area(Areas::Circle(3)),
+            effect: MagicEffects::FireArea,
+            shoot: Some(ShootTypes::Fire),
+            action: SpellActions::Damage {
+                min: 20,
+                max: 60,
+                effect: MagicEffects::HitByFire,
+                color: TextColors::Orange,
+            },
+        },
+        Spell {
+            name: "Sudden Death Rune".into(),
+            trigger: SpellTriggers::Rune(Items::SuddenDeathRune),
+            level: 45,
+            magic_level: 15,
+            mana: 0,
+            soul: 0,
+            cooldown: Duration::from_secs(2),
+            area: SpellAreas::Creature,
+            effect: MagicEffects::MortArea,
+            shoot: Some(ShootTypes::Death),
+            action: SpellActions::Damage {
+                min: 150,
+                max: 300,
+                effect: MagicEffects::MortArea,
+                color: TextColors::DarkRed,
+            },
+        },
+    ]
+}
+
+/// Random amount between `min` and `max`, raised by the caster's level and magic level.
+fn amount(caster: &Entity, min: u16, max: u16) -> u16 {
+    let level = caster.level().map_or(1, |Level(level)| *level);
+    let magic_level = caster
+        .magic_level()
+        .map_or(0, |magic_level| magic_level.level);
+    fastrand::u16(min..=max) + (level * 2 + magic_level * 3) / 5
+}
+
+/// Requirements and costs of casting, the failure message when the caster cannot afford it.
+fn pay(id: EntityId, caster: &Entity, spell: &Spell) -> Result<CommandsType, &'static str> {
+    caster.player().ok_or(SORRY)?;
+    let player = caster.creature_id().ok_or(SORRY)?;
+    let ready_at = caster
+        .cooldowns()
+        .and_then(|Cooldowns(cooldowns)| cooldowns.get(&spell.name).copied());
+    if ready_at.is_some_and(|ready_at| ready_at > Instant::now()) {
+        return Err(EXHAUSTED);
+    }
+    if caster.level().map_or(0, |Level(level)| *level) < spell.level {
+        return Err(NOT_ENOUGH_LEVEL);
+    }
+    if caster
+        .magic_level()
+        .map_or(0, |magic_level| magic_level.level)
+        < spell.magic_level
+    {
+        return Err(NOT_ENOUGH_MAGIC_LEVEL);
+    }
+    let Mana {
+        value: mana,
+        max: mana_max,
+    } = caster.mana().cloned().unwrap_or(Mana { value: 0, max: 0 });
+    if mana < spell.mana {
+        return Err(NOT_ENOUGH_MANA);
+    }
+    let soul = caster.soul().map_or(0, |Soul(soul)| *soul);
+    if soul < spell.soul {
+        return Err(NOT_ENOUGH_SOUL);
+    }
+
+    let mut cooldowns = caster
+        .cooldowns()
+        .cloned()
+        .unwrap_or_else(|| Cooldowns(HashMap::new()));
+    cooldowns
+        .0
+        .insert(spell.name.clone(), Instant::now() + spell.cooldown);
+    let mut commands = vec![Box::new(SetEntityAttributeCommand {
+        entity: id,
+        attribute: Box::new(cooldowns),
+    }) as CommandType];
+    if spell.mana > 0 {
+        commands.push(Box::new(SetEntityAttributeCommand {
+            entity: id,
+            attribute: Box::new(Mana {
+                value: mana - spell.mana,
+                max: mana_max,
+            }),
+        }) as CommandType);
+        commands.push(Box::new(AddManaSpentCommand {
+            player: player.clone(),
+            mana: spell.mana.into(),
+        }) as CommandType);
+    }
+    if spell.soul > 0 {
+        commands.push(Box::new(SetEntityAttributeCommand {
+            entity: id,
+            attribute: Box::new(Soul(soul - spell.soul)),
+        }) as CommandType);
+    }
+    Ok(commands)
+}
+
+fn cast(
+    world: &World,
+    position: &Position,
+    caster: &Entity,
+    spell: &Spell,
+    target: &Position,
+) -> CommandsType {
+    let mut commands = Vec::new();
+    if let Some(shoot) = spell.shoot {
+        commands.push(Box::new(ShowEffectCommand {
+            position: position.clone(),
+            effect: VisualEffects::Distance {
+                to: target.clone(),
+                shoot,
+            },
+        }) as CommandType);
+    }
+    let direction = caster
+        .direction()
+        .map_or(Directions::None, |Direction(direction)| *direction);
+    // the area stops at walls, as seen from where it is laid out
+    let area = spell.area.positions(target, direction);
+    for tile_position in area
+        .into_iter()
+        .filter(|tile_position| geometry::is_sight_clear(world, target, tile_position))
+    {
+        if world.tile(&tile_position).is_none() {
+            continue;
+        }
+        commands.push(Box::new(ShowEffectCommand {
+            position: tile_position.clone(),
+            effect: VisualEffects::Magic(spell.effect),
+        }) as CommandType);
+        for (id, entity) in world.entities(&tile_position) {
+            let Some(creature) = entity.creature_id() else {
+                continue;
+            };
+            match spell.action {
+                SpellActions::Heal { min, max } => {
+                    let Some(Health {
+                        value,
+                        max: health_max,
+                    }) = entity.health()
+                    else {
+                        continue;
+                    };
+                    commands.push(Box::new(SetEntityAttributeCommand {
+                        entity: id,
+                        attribute: Box::new(Health {
+                            value: (value + amount(caster, min, max)).min(*health_max),
+                            max: *health_max,
+                        }),
+                    }) as CommandType);
+                }
+                SpellActions::Damage {
+                    min,
+                    max,
+                    effect,
+                    color,
+                } => {
+                    if Some(creature) == caster.creature_id() || entity.npc().is_some() {
+                        continue;
+                    }
+                    commands.append(&mut combat::damage(
+                        world,
+                        id,
+                        amount(caster, min, max),
+                        effect,
+                        color,
+                    ));
+                }
+                SpellActions::Condition {
+                    condition,
+                    duration,
+                    damage,
+                } => {
+                    // conditions are only kept track of for players so far
+                    if entity.player().is_none() {
+                        continue;
+                    }
+                    commands.push(Box::new(AddConditionCommand {
+                        player: creature.clone(),
+                        condition,
+                        duration,
+                        damage,
+                    }) as CommandType);
+                }
+            }
+        }
+    }
+    commands
+}
+
+/// Uses up one charge of the rune lying at `source`, on the ground or in the caster's inventory.
+fn use_charge(
+    world: &World,
+    id: EntityId,
+    caster: &Entity,
+    source: &Position,
+) -> Option<CommandType> {
+    let (rune, slot) = match Inventory::slot(source) {
+        Some(slot) => (caster.inventory()?.at(source)?, Some(slot)),
+        None => (world.entity(world.entity_at(source)?)?, None),
+    };
+    let charges = rune.count().map_or(1, |Count(count)| *count);
+    let command = match slot {
+        Some(slot) => {
+            let Inventory(mut inventory) = caster.inventory()?.clone();
+            if charges > 1 {
+                let mut rune = rune.clone();
+                let count = Count(charges - 1);
+                rune.attributes
+                    .insert(count.as_name().to_string(), Box::new(count));
+                inventory.insert(slot, rune);
+            } else {
+                inventory.remove(&slot);
+            }
+            Box::new(SetEntityAttributeCommand {
+                entity: id,
+                attribute: Box::new(Inventory(inventory)),
+            }) as CommandType
+        }
+        None if charges > 1 => Box::new(SetEntityAttributeCommand {
+            entity: world.entity_at(source)?,
+            attribute: Box::new(Count(charges - 1)),
+        }) as CommandType,
+        None => Box::new(RemoveEntityCommand {
+            entity: world.entity_at(source)?,
+        }) as CommandType,
+    };
+    Some(command)
+}
+
+system! {
+    #[effect(SystemsLoadedEvent)]
+    fn init_spells_attribute(_: EventType, _: GameAttributesType, _: WorldType) -> EffectResultType {
+        let commands = default_spells()
+            .into_iter()
+            .map(|spell| Box::new(RegisterSpellCommand { spell }) as CommandType)
+            .collect();
+        Some((commands, Vec::new()))
+    }
+
+    // spells registered by other systems are kept no matter which system loads first
+    #[handler(RegisterSpellCommand)]
+    fn handle_register_spell(command: CommandType, attributes: GameAttributesType, _: WorldType) -> EffectResultType {
+        let RegisterSpellCommand { spell } = command.as_any().downcast_ref::<RegisterSpellCommand>().unwrap();
+        let game_attributes = attributes.lock().unwrap();
+        let mut spells = game_attributes.spells().cloned().unwrap_or_else(|| Spells(Vec::new()));
+        spells.0.retain(|registered| registered.name != spell.name);
+        spells.0.push(spell.clone());
+        let commands = vec![Box::new(SetGameAttributeCommand(Box::new(spells))) as CommandType];
+        Some((commands, Vec::new()))
+    }
+
+    #[effect(SpeakEvent)]
+    fn handle_spell_words(event: EventType, attributes: GameAttributesType, world: WorldType) -> EffectResultType {
+        let SpeakEvent { player, position, class, text } = event.as_any().downcast_ref::<SpeakEvent>().unwrap();
+        let mut commands = Vec::new();
+        if *class != SpeakClasses::Say {
+            return Some((commands, Vec::new()));
+        }
+        let game_attributes = attributes.lock().unwrap();
+        let world = world.lock().unwrap();
+
+        if let Some(mut spell_commands) = (|| {
+            let player = player.as_ref()?;
+            let spell = game_attributes.spells()?.by_words(text.trim())?;
+            let id = world.find_creature(player)?;
+            let caster = world.entity(id)?;
+            let mut commands = match pay(id, caster, spell) {
+                Ok(commands) => commands,
+                Err(message) => return Some(vec![Box::new(SendTextCommand::cancel(player.clone(), message)) as CommandType]),
+            };
+            commands.append(&mut cast(&world, position, caster, spell, position));
+            Some(commands)
+        })() {
+            commands.append(&mut spell_commands);
+        }
+
+        Some((commands, Vec::new()))
+    }
+
+    #[effect(UseEvent)]
+    fn handle_rune_use(event: EventType, attributes: GameAttributesType, world: WorldType) -> EffectResultType {
+        let UseEvent { player, source, target } = event.as_any().downcast_ref::<UseEvent>().unwrap();
+        let mut commands = Vec::new();
+        let game_attributes = attributes.lock().unwrap();
+        let world = world.lock().unwrap();
+
+        if let Some(mut rune_commands) = (|| {
+            let player = player.as_ref()?;
+            let source = source.as_ref()?;
+            let id = world.find_creature(player)?;
+            let caster = world.entity(id)?;
+            let position = world.position(id)?.stack_pos(None);
+            let rune = match Inventory::slot(source) {
+                Some(_) => caster.inventory()?.at(source)?,
+                None => world.entity(world.entity_at(source)?)?,
+            };
+            let spell = game_attributes.spells()?.by_rune(Items::try_from(rune.item()?.0).ok()?)?;
+            let cancel = |message| Some(vec![Box::new(SendTextCommand::cancel(player.clone(), message)) as CommandType]);
+
+            let target = world.position(*target)?.stack_pos(None);
+            if geometry::distance(&position, &target) > RUNE_RANGE || !geometry::is_sight_clear(&world, &position, &target) {
+                return cancel(SORRY);
+            }
+            let has_creature = world.entities(&target).any(|(_, entity)| entity.creature_id().is_some());
+            if matches!(spell.area, SpellAreas::Creature) && !has_creature {
+                return cancel(ONLY_ON_CREATURES);
+            }
+            let mut commands = match pay(id, caster, spell) {
+                Ok(commands) => commands,
+                Err(message) => return cancel(message),
+            };
+            commands.extend(use_charge(&world, id, caster, source));
+            commands.append(&mut cast(&world, &position, caster, spell, &target));
+            Some(commands)
+        })() {
+            commands.append(&mut rune_commands);
+        }
+
+        Some((commands, Vec::new()))
+    }
+}
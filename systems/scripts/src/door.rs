@@ -0,0 +1,135 @@
+use skyless_core::{prelude::*, World};
+use skyless_systems_core::prelude::*;
+use std::convert::TryInto;
+
+const LOCKED: &str = "It is locked.";
+const NOT_WORTHY: &str = "Only the worthy may pass.";
+
+fn is_vacant(world: &World, position: &Position) -> bool {
+    world
+        .entities(position)
+        .all(|(_, entity)| entity.player().is_none())
+}
+
+system! {
+    #[effect(UseEvent)]
+    fn handle_door_use(event: EventType, _: GameAttributesType, world: WorldType) -> EffectResultType {
+        println!("Handle door use");
+        let UseEvent { player, source, target } = event.as_any().downcast_ref::<UseEvent>().unwrap();
+        let mut commands = Vec::new();
+        let world = world.lock().unwrap();
+
+        if let Some(mut door_commands) = (|| {
+            let entity = world.entity(*target)?;
+            let target_position = world.position(*target)?;
+            match entity.action()? {
+                Action(Actions::Door) => {
+                    let door_attribute = entity.door()?;
+                    let Door(door) = door_attribute;
+                    let item: Items = entity.item()?.0.try_into().ok()?;
+                    let mut commands = Vec::new();
+                    match source {
+                        Some(source) => {
+                            let key = match Inventory::slot(source) {
+                                Some(_) => {
+                                    let player = world.find_creature(player.as_ref()?)?;
+                                    world.entity(player)?.inventory()?.at(source)?
+                                },
+                                None => world.entity(world.entity_at(source)?)?,
+                            };
+                            let _ = key.item().filter(|Item(id)| *id == u16::from(Items::Key))?;
+                            if key.action_id()?.0 != entity.action_id()?.0 {
+                                return None;
+                            }
+                            let new_item = match item {
+                                Items::LockedDoor => item.opened(),
+                                _ if is_vacant(&world, &target_position) => item.locked(),
+                                _ => None,
+                            }?;
+                            commands.push(Box::new(SetEntityAttributeCommand {
+                                entity: *target,
+                                attribute: Box::new(Item(new_item.into())),
+                            }) as CommandType);
+                        },
+                        None => match door {
+                            Doors::Regular => match item {
+                                Items::LockedDoor => {
+                                    commands.push(Box::new(SendTextCommand::cancel(player.clone()?, LOCKED)) as CommandType);
+                                },
+                                _ => {
+                                    let new_item = match item.opened() {
+                                        Some(opened) => Some(opened),
+                                        None if is_vacant(&world, &target_position) => item.closed(),
+                                        None => None,
+                                    }?;
+                                    commands.push(Box::new(SetEntityAttributeCommand {
+                                        entity: *target,
+                                        attribute: Box::new(Item(new_item.into())),
+                                    }) as CommandType);
+                                },
+                            },
+                            Doors::Level(_) | Doors::Quest(_) => {
+                                let new_item = item.opened()?;
+                                let player = player.as_ref()?;
+                                let player_id = world.find_creature(player)?;
+                                let player_entity = world.entity(player_id)?;
+                                let player_position = world.position(player_id)?.stack_pos(None);
+                                let _ = Direction::between_positions(player_position.clone(), target_position.clone())
+                                    .filter(|Direction(direction)| !matches!(direction, Directions::None))?;
+                                if !door_attribute.can_pass(player_entity) {
+                                    commands.push(Box::new(SendTextCommand::cancel(player.clone(), NOT_WORTHY)) as CommandType);
+                                    return Some(commands);
+                                }
+                                commands.push(Box::new(SetEntityAttributeCommand {
+                                    entity: *target,
+                                    attribute: Box::new(Item(new_item.into())),
+                                }) as CommandType);
+                                commands.push(Box::new(EmitEventCommand(Arc::new(MoveEvent {
+                                    from: player_position,
+                                    to: target_position.stack_pos(None),
+                                    creature: player.clone(),
+                                }))) as CommandType);
+                            },
+                        },
+                    };
+                    Some(commands)
+                },
+                _ => None
+            }
+        })() {
+            commands.append(&mut door_commands);
+        }
+
+        Some((commands, Vec::new()))
+    }
+
+    // doors close by themselves once the doorway is vacated
+    #[effect(SeparationEvent)]
+    fn handle_door_separation(event: EventType, _: GameAttributesType, world: WorldType) -> EffectResultType {
+        println!("Handle door separation");
+        let SeparationEvent { first, second } = event.as_any().downcast_ref::<SeparationEvent>().unwrap();
+        let mut commands = Vec::new();
+        let world = world.lock().unwrap();
+        if let Some(command) = (|| {
+            let first_entity = world.entity(*first)?;
+            match first_entity.action()? {
+                Action(Actions::Door) => {
+                    let _ = world.entity(*second)?.player()?;
+                    first_entity.door()?;
+                    if !is_vacant(&world, &world.position(*first)?) {
+                        return None;
+                    }
+                    let item: Items = first_entity.item()?.0.try_into().ok()?;
+                    Some(Box::new(SetEntityAttributeCommand {
+                        entity: *first,
+                        attribute: Box::new(Item(item.closed()?.into())),
+                    }) as CommandType)
+                },
+                _ => None,
+            }
+        })() {
+            commands.push(command);
+        }
+        Some((commands, Vec::new()))
+    }
+}
@@ -1,3 +1,4 @@
+use super::link;
 use skyless_core::prelude::*;
 use skyless_systems_core::prelude::*;
 use std::convert::TryInto;
@@ -8,29 +9,43 @@ system! {
         println!("Handle lever use");
         let UseEvent { target, .. } = event.as_any().downcast_ref::<UseEvent>().unwrap();
         let mut commands = Vec::new();
+        let mut tasks = Vec::new();
         let world = world.lock().unwrap();
 
-        if let Some(command) = (|| {
-            let entity = world.entity(target)?;
+        if let Some((mut lever_commands, mut lever_tasks)) = (|| {
+            let entity = world.entity(*target)?;
             match entity.action()? {
                 Action(Actions::Lever) => {
                     let entity_item = entity.item()?;
-                    let new_item = match entity_item.0.try_into().unwrap() {
-                        Items::LeverLeft => Some(Items::LeverRight),
-                        Items::LeverRight => Some(Items::LeverLeft),
+                    let (new_item, active) = match entity_item.0.try_into().unwrap() {
+                        Items::LeverLeft => Some((Items::LeverRight, true)),
+                        Items::LeverRight => Some((Items::LeverLeft, false)),
                         _ => None,
                     }?;
-                    Some(Box::new(SetEntityAttributeCommand {
-                        position: target.clone(),
+                    let mut commands = vec![Box::new(SetEntityAttributeCommand {
+                        entity: *target,
                         attribute: Box::new(Item(new_item.into())),
-                    }) as CommandType)
+                    }) as CommandType];
+                    let mut tasks = Vec::new();
+                    if let Some(entity_link) = entity.link() {
+                        if active {
+                            commands.append(&mut link::apply(&world, entity_link));
+                            if let Some(reset) = entity_link.reset {
+                                tasks.push(link::reset_task(*target, Items::LeverLeft, reset));
+                            }
+                        } else {
+                            commands.append(&mut link::revert(&world, entity_link));
+                        }
+                    }
+                    Some((commands, tasks))
                 },
                 _ => None
             }
         })() {
-            commands.push(command);
+            commands.append(&mut lever_commands);
+            tasks.append(&mut lever_tasks);
         }
 
-        Some((commands, Vec::new()))
+        Some((commands, tasks))
     }
 }
@@ -1,5 +1,9 @@
 #![forbid(unsafe_code)]
 
+pub mod door;
 pub mod lever;
+pub mod link;
+pub mod npc;
 pub mod switch;
+pub mod teleport;
 pub mod tick;
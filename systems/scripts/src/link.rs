@@ -0,0 +1,95 @@
+use skyless_core::{prelude::*, World};
+use skyless_systems_core::prelude::*;
+use std::time::Duration;
+use tokio::time;
+
+#[event]
+pub struct LinkResetEvent {
+    pub trigger: EntityId,
+    pub item: Items,
+}
+
+pub fn apply(world: &World, link: &Link) -> CommandsType {
+    link.targets
+        .iter()
+        .filter_map(|LinkTarget { position, effect }| match effect {
+            LinkEffect::Transform { from, to } => transform(world, position, *from, *to),
+            LinkEffect::Create(item) => create(position, *item),
+            LinkEffect::Remove(item) => remove(world, position, *item),
+        })
+        .collect()
+}
+
+pub fn revert(world: &World, link: &Link) -> CommandsType {
+    link.targets
+        .iter()
+        .filter_map(|LinkTarget { position, effect }| match effect {
+            LinkEffect::Transform { from, to } => transform(world, position, *to, *from),
+            LinkEffect::Create(item) => remove(world, position, *item),
+            LinkEffect::Remove(item) => create(position, *item),
+        })
+        .collect()
+}
+
+pub fn reset_task(trigger: EntityId, item: Items, reset: Duration) -> TaskType {
+    Box::pin(stream! {
+        time::sleep(reset).await;
+        yield Some(Arc::new(LinkResetEvent { trigger, item }) as EventType);
+    })
+}
+
+fn find_item(world: &World, position: &Position, item: Items) -> Option<EntityId> {
+    world
+        .entities(position)
+        .find(|(_, entity)| entity.item().is_some_and(|Item(id)| *id == item.into()))
+        .map(|(id, _)| id)
+}
+
+fn transform(world: &World, position: &Position, from: Items, to: Items) -> Option<CommandType> {
+    Some(Box::new(SetEntityAttributeCommand {
+        entity: find_item(world, position, from)?,
+        attribute: Box::new(Item(to.into())),
+    }) as CommandType)
+}
+
+fn create(position: &Position, item: Items) -> Option<CommandType> {
+    Some(Box::new(AddEntityCommand {
+        position: position.clone(),
+        entity: entity![Item(item.into())],
+    }) as CommandType)
+}
+
+fn remove(world: &World, position: &Position, item: Items) -> Option<CommandType> {
+    Some(Box::new(RemoveEntityCommand {
+        entity: find_item(world, position, item)?,
+    }) as CommandType)
+}
+
+system! {
+    #[effect(LinkResetEvent)]
+    fn handle_link_reset(event: EventType, _: GameAttributesType, world: WorldType) -> EffectResultType {
+        println!("Handle link reset");
+        let LinkResetEvent { trigger, item } = event.as_any().downcast_ref::<LinkResetEvent>().unwrap();
+        let mut commands = Vec::new();
+        let world = world.lock().unwrap();
+
+        if let Some(mut reset_commands) = (|| {
+            let entity = world.entity(*trigger)?;
+            let Item(current) = entity.item()?;
+            if *current == u16::from(*item) {
+                return None;
+            }
+            let link = entity.link()?;
+            let mut commands = vec![Box::new(SetEntityAttributeCommand {
+                entity: *trigger,
+                attribute: Box::new(Item((*item).into())),
+            }) as CommandType];
+            commands.append(&mut revert(&world, link));
+            Some(commands)
+        })() {
+            commands.append(&mut reset_commands);
+        }
+
+        Some((commands, Vec::new()))
+    }
+}
@@ -0,0 +1,22 @@
+use skyless_core::prelude::*;
+use skyless_systems_core::npcs::{says, DialogueEvent, NpcSayEvent};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+system! {
+    #[effect(DialogueEvent)]
+    fn handle_time_question(event: EventType, _: GameAttributesType, _: WorldType) -> EffectResultType {
+        let DialogueEvent { npc, text, .. } = event.as_any().downcast_ref::<DialogueEvent>().unwrap();
+        let mut commands = Vec::new();
+
+        if says(text, "time") {
+            let seconds = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |duration| duration.as_secs());
+            let (hours, minutes) = (seconds / 3600 % 24, seconds / 60 % 60);
+            commands.push(Box::new(EmitEventCommand(Arc::new(NpcSayEvent {
+                npc: npc.clone(),
+                text: format!("It is about {:02}:{:02}.", hours, minutes),
+            }))) as CommandType);
+        }
+
+        Some((commands, Vec::new()))
+    }
+}
@@ -1,3 +1,4 @@
+use super::link;
 use skyless_core::{prelude::*, CommandType};
 use skyless_systems_core::prelude::*;
 
@@ -7,25 +8,35 @@ system! {
         println!("Handle switch collision");
         let CollisionEvent { first, second } = event.as_any().downcast_ref::<CollisionEvent>().unwrap();
         let mut commands = Vec::new();
+        let mut tasks = Vec::new();
         let world = world.lock().unwrap();
-        if let Some(command) = {
-            let first_entity = world.entity(first)?;
+        if let Some((mut switch_commands, mut switch_tasks)) = {
+            let first_entity = world.entity(*first)?;
             match first_entity.action()? {
                 Action(Actions::Switch) => {
-                    let second_entity = world.entity(second)?;
+                    let second_entity = world.entity(*second)?;
                     let _ = second_entity.player()?;
                     println!("Activate switch");
-                    Some(Box::new(SetEntityAttributeCommand {
-                        position: first.clone(),
+                    let mut commands = vec![Box::new(SetEntityAttributeCommand {
+                        entity: *first,
                         attribute: Box::new(Item(Items::StoneSwitchActivated.into())),
-                    }) as CommandType)
+                    }) as CommandType];
+                    let mut tasks = Vec::new();
+                    if let Some(entity_link) = first_entity.link() {
+                        commands.append(&mut link::apply(&world, entity_link));
+                        if let Some(reset) = entity_link.reset {
+                            tasks.push(link::reset_task(*first, Items::StoneSwitch, reset));
+                        }
+                    }
+                    Some((commands, tasks))
                 },
                 _ => None
             }
         } {
-            commands.push(command);
+            commands.append(&mut switch_commands);
+            tasks.append(&mut switch_tasks);
         }
-        Some((commands, Vec::new()))
+        Some((commands, tasks))
     }
 
     #[effect(SeparationEvent)]
@@ -34,22 +45,30 @@ system! {
         let SeparationEvent { first, second } = event.as_any().downcast_ref::<SeparationEvent>().unwrap();
         let mut commands = Vec::new();
         let world = world.lock().unwrap();
-        if let Some(command) = {
-            let first_entity = world.entity(first)?;
+        if let Some(mut switch_commands) = {
+            let first_entity = world.entity(*first)?;
             match first_entity.action()? {
                 Action(Actions::Switch) => {
-                    let second_entity = world.entity(second)?;
+                    let second_entity = world.entity(*second)?;
                     let _ = second_entity.player()?;
+                    // switches with an auto-reset timer stay activated until the timer fires
+                    if first_entity.link().is_some_and(|entity_link| entity_link.reset.is_some()) {
+                        return None;
+                    }
                     println!("Deactivate switch");
-                    Some(Box::new(SetEntityAttributeCommand {
-                        position: first.clone(),
+                    let mut commands = vec![Box::new(SetEntityAttributeCommand {
+                        entity: *first,
                         attribute: Box::new(Item(Items::StoneSwitch.into())),
-                    }) as CommandType)
+                    }) as CommandType];
+                    if let Some(entity_link) = first_entity.link() {
+                        commands.append(&mut link::revert(&world, entity_link));
+                    }
+                    Some(commands)
                 },
                 _ => None,
             }
         } {
-            commands.push(command);
+            commands.append(&mut switch_commands);
         }
         Some((commands, Vec::new()))
     }
@@ -0,0 +1,27 @@
+use skyless_core::prelude::*;
+use skyless_systems_core::prelude::*;
+
+system! {
+    #[effect(CollisionEvent)]
+    fn handle_teleport_collision(event: EventType, _: GameAttributesType, world: WorldType) -> EffectResultType {
+        println!("Handle teleport collision");
+        let CollisionEvent { first, second } = event.as_any().downcast_ref::<CollisionEvent>().unwrap();
+        let mut tasks = Vec::new();
+        let world = world.lock().unwrap();
+        if let Some(task) = {
+            let Teleport(destination) = world.entity(*first)?.teleport()?;
+            world.entity(*second)?.creature_id()?;
+            let event = Arc::new(TeleportEvent {
+                entity: *second,
+                to: destination.clone(),
+            }) as EventType;
+            // emitted from a task so the step onto the teleport is finished first
+            Some(Box::pin(stream! {
+                yield Some(event);
+            }) as TaskType)
+        } {
+            tasks.push(task);
+        }
+        Some((Vec::new(), tasks))
+    }
+}